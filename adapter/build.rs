@@ -0,0 +1,4 @@
+// sqlx::migrate! はビルド時にマイグレーションを埋め込むため、追加・変更されたら再ビルドする
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
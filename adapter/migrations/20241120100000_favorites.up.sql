@@ -0,0 +1,16 @@
+-- ユーザーごとのお気に入り蔵書
+CREATE TABLE IF NOT EXISTS favorites (
+  user_id UUID NOT NULL,
+  book_id UUID NOT NULL,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  -- 同じ蔵書を二重に登録できないようにする
+  PRIMARY KEY (user_id, book_id),
+
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE,
+  FOREIGN KEY (book_id) REFERENCES books(book_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
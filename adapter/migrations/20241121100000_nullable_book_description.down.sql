@@ -0,0 +1,2 @@
+UPDATE books SET description = '' WHERE description IS NULL;
+ALTER TABLE books ALTER COLUMN description SET NOT NULL;
@@ -0,0 +1,2 @@
+-- 説明のない蔵書を扱えるよう、books.description を NULL 許容にする
+ALTER TABLE books ALTER COLUMN description DROP NOT NULL;
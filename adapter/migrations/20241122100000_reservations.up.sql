@@ -0,0 +1,20 @@
+-- 貸出中の蔵書に対する予約（順番待ち）
+CREATE TABLE IF NOT EXISTS reservations (
+  reservation_id UUID PRIMARY KEY NOT NULL,
+  book_id UUID NOT NULL,
+  user_id UUID NOT NULL,
+  -- 予約を受け付けた順番。同じ時刻の予約でも順番待ちの位置が一意に決まるようにする
+  queued_order BIGINT GENERATED ALWAYS AS IDENTITY,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  FOREIGN KEY (book_id) REFERENCES books(book_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE,
+  FOREIGN KEY (user_id) REFERENCES users(user_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+
+-- 同じユーザーが同じ蔵書を二重に予約できないようにする
+CREATE UNIQUE INDEX IF NOT EXISTS reservations_book_id_user_id_key
+  ON reservations (book_id, user_id);
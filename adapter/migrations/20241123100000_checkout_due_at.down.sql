@@ -0,0 +1 @@
+ALTER TABLE checkouts DROP COLUMN due_at;
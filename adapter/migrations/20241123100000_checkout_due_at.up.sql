@@ -0,0 +1,5 @@
+-- 延滞を検知できるよう、checkouts に返却期限（due_at）を追加する
+-- 既存の貸出は、貸出日から貸出期間の既定値（14日）が経過した日時を期限とする
+ALTER TABLE checkouts ADD COLUMN due_at TIMESTAMP(3) WITH TIME ZONE;
+UPDATE checkouts SET due_at = checked_out_at + INTERVAL '14 days';
+ALTER TABLE checkouts ALTER COLUMN due_at SET NOT NULL;
@@ -0,0 +1,2 @@
+DELETE FROM books WHERE deleted_at IS NOT NULL;
+ALTER TABLE books DROP COLUMN deleted_at;
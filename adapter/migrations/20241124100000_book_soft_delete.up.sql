@@ -0,0 +1,2 @@
+-- 蔵書を削除しても貸出履歴が失われないよう、行を消さずに削除日時を記録する
+ALTER TABLE books ADD COLUMN deleted_at TIMESTAMP(3) WITH TIME ZONE;
@@ -0,0 +1,3 @@
+-- 同じ ISBN の蔵書を重複して登録できないようにする。
+-- 削除済みの蔵書は対象外とし、同じ ISBN で登録し直せるようにする
+CREATE UNIQUE INDEX books_isbn_key ON books (isbn) WHERE deleted_at IS NULL;
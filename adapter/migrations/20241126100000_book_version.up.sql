@@ -0,0 +1,2 @@
+-- 同時に編集された蔵書の更新が失われないよう、更新のたびに増える版番号を持たせる
+ALTER TABLE books ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
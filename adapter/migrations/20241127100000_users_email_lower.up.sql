@@ -0,0 +1,2 @@
+-- 大文字・小文字だけが異なるメールアドレスで重複してユーザーを登録できないようにする
+CREATE UNIQUE INDEX users_email_lower_key ON users (LOWER(email));
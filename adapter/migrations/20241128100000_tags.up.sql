@@ -0,0 +1,26 @@
+-- 蔵書の分類に使うタグ。名前は小文字に揃えて保存する
+CREATE TABLE IF NOT EXISTS tags (
+  tag_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  name VARCHAR(50) NOT NULL UNIQUE,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3)
+);
+
+-- 蔵書とタグの多対多の対応
+CREATE TABLE IF NOT EXISTS book_tags (
+  book_id UUID NOT NULL,
+  tag_id UUID NOT NULL,
+  created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+  -- 同じタグを二重に付けられないようにする
+  PRIMARY KEY (book_id, tag_id),
+
+  FOREIGN KEY (book_id) REFERENCES books(book_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE,
+  FOREIGN KEY (tag_id) REFERENCES tags(tag_id)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+
+-- タグで蔵書を絞り込むときに使う
+CREATE INDEX IF NOT EXISTS book_tags_tag_id_idx ON book_tags (tag_id);
@@ -0,0 +1,4 @@
+-- ハイフンの有無だけが異なる ISBN で重複して蔵書を登録できないよう、ハイフンを除いた値で一意にする。
+-- ISBN での検索もこの式で行うため、同じインデックスが使われる
+DROP INDEX books_isbn_key;
+CREATE UNIQUE INDEX books_isbn_key ON books (REPLACE(isbn, '-', '')) WHERE deleted_at IS NULL;
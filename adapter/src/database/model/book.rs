@@ -4,19 +4,28 @@ use kernel::model::{
     id::{BookId, CheckoutId, UserId},
     user::{BookOwner, CheckoutUser},
 };
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
 
+use crate::redis::model::{RedisKey, RedisValue};
+
+#[derive(Serialize, Deserialize)]
 pub struct BookRow {
     pub book_id: BookId,
     pub title: String,
     pub author: String,
     pub isbn: String,
-    pub description: String,
+    pub description: Option<String>,
     pub owned_by: UserId,
     pub owner_name: String,
+    pub owner_email: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl BookRow {
-    pub fn into_book(self, checkout: Option<Checkout>) -> Book {
+    pub fn into_book(self, checkout: Option<Checkout>, tags: Vec<String>) -> Book {
         let BookRow {
             book_id,
             title,
@@ -25,6 +34,10 @@ impl BookRow {
             description,
             owned_by,
             owner_name,
+            owner_email,
+            version,
+            created_at,
+            updated_at,
         } = self;
         Book {
             id: book_id,
@@ -35,12 +48,44 @@ impl BookRow {
             owner: BookOwner {
                 id: owned_by,
                 name: owner_name,
+                email: owner_email,
             },
             checkout,
+            tags,
+            version,
+            created_at,
+            updated_at,
         }
     }
 }
 
+// 蔵書の詳細のキャッシュのキー。認証トークンのキーと衝突しないよう接頭辞を付ける。
+// 貸出状態は変わりやすいためキャッシュせず、取得のたびにデータベースから読む。
+// タグも TagRepository から更新されキャッシュを消せないため、同様に毎回読む
+pub struct BookCacheKey(pub BookId);
+
+impl RedisKey for BookCacheKey {
+    type Value = BookRow;
+
+    fn inner(&self) -> String {
+        format!("book:{}", self.0)
+    }
+}
+
+impl RedisValue for BookRow {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).expect("BookRow is always serializable")
+    }
+}
+
+impl TryFrom<String> for BookRow {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
 pub struct PaginatedBookRow {
     pub total: i64,
     pub id: BookId,
@@ -54,6 +99,11 @@ pub struct BookCheckoutRow {
     pub checked_out_at: DateTime<Utc>,
 }
 
+pub struct BookTagsRow {
+    pub book_id: BookId,
+    pub tags: Vec<String>,
+}
+
 impl From<BookCheckoutRow> for Checkout {
     fn from(value: BookCheckoutRow) -> Self {
         let BookCheckoutRow {
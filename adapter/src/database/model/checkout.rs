@@ -1,5 +1,5 @@
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
+    checkout::{Checkout, CheckoutBook, LostCheckout, OverdueCheckout},
     id::{BookId, CheckoutId, UserId},
 };
 use sqlx::types::chrono::{DateTime, Utc};
@@ -52,21 +52,60 @@ impl From<CheckoutRow> for Checkout {
     }
 }
 
-// 返却済みの貸し出し一覧を取得する際に使う型
-pub struct ReturnedCheckoutRow {
+// 貸出中の一覧をページ単位で取得する際に使う型
+pub struct PaginatedCheckoutRow {
+    pub total: i64,
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
     pub user_id: UserId,
     pub checked_out_at: DateTime<Utc>,
-    pub returned_at: DateTime<Utc>,
     pub title: String,
     pub author: String,
     pub isbn: String,
 }
 
-impl From<ReturnedCheckoutRow> for Checkout {
-    fn from(value: ReturnedCheckoutRow) -> Self {
-        let ReturnedCheckoutRow {
+impl From<PaginatedCheckoutRow> for Checkout {
+    fn from(value: PaginatedCheckoutRow) -> Self {
+        let PaginatedCheckoutRow {
+            checkout_id,
+            book_id,
+            user_id,
+            checked_out_at,
+            title,
+            author,
+            isbn,
+            ..
+        } = value;
+        CheckoutRow {
+            checkout_id,
+            book_id,
+            user_id,
+            checked_out_at,
+            title,
+            author,
+            isbn,
+        }
+        .into()
+    }
+}
+
+// 貸出中・返却済みをあわせた貸出履歴を取得する際に使う型
+// 貸出中の場合は returned_at が None になる
+pub struct CheckoutHistoryRow {
+    pub total: i64,
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub checked_out_at: DateTime<Utc>,
+    pub returned_at: Option<DateTime<Utc>>,
+    pub title: String,
+    pub author: String,
+    pub isbn: String,
+}
+
+impl From<CheckoutHistoryRow> for Checkout {
+    fn from(value: CheckoutHistoryRow) -> Self {
+        let CheckoutHistoryRow {
             checkout_id,
             book_id,
             user_id,
@@ -75,13 +114,13 @@ impl From<ReturnedCheckoutRow> for Checkout {
             title,
             author,
             isbn,
+            ..
         } = value;
         Checkout {
             id: checkout_id,
             checked_out_by: user_id,
             checked_out_at,
-            // 返却済みなので returned_at には日時データが入る
-            returned_at: Some(returned_at),
+            returned_at,
             book: CheckoutBook {
                 book_id,
                 title,
@@ -91,3 +130,76 @@ impl From<ReturnedCheckoutRow> for Checkout {
         }
     }
 }
+
+// 紛失の疑いがある貸出の一覧を取得する際に使う型
+pub struct LostCheckoutRow {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+}
+
+impl LostCheckoutRow {
+    // 紛失の判定は返却期限ではなく、貸出日からの経過日数で行う
+    pub fn into_lost_checkout(self, now: DateTime<Utc>) -> LostCheckout {
+        let LostCheckoutRow {
+            checkout_id,
+            book_id,
+            title,
+            user_id,
+            user_name,
+            email,
+            checked_out_at,
+        } = self;
+        LostCheckout {
+            checkout_id,
+            book_id,
+            title,
+            checked_out_by: user_id,
+            user_name,
+            email,
+            checked_out_at,
+            days_overdue: (now - checked_out_at).num_days(),
+        }
+    }
+}
+
+// 返却期限を過ぎた貸出の一覧を取得する際に使う型
+pub struct OverdueCheckoutRow {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+impl From<OverdueCheckoutRow> for OverdueCheckout {
+    fn from(value: OverdueCheckoutRow) -> Self {
+        let OverdueCheckoutRow {
+            checkout_id,
+            book_id,
+            title,
+            user_id,
+            user_name,
+            email,
+            checked_out_at,
+            due_at,
+        } = value;
+        OverdueCheckout {
+            checkout_id,
+            book_id,
+            title,
+            checked_out_by: user_id,
+            user_name,
+            email,
+            checked_out_at,
+            due_at,
+        }
+    }
+}
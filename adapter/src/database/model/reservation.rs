@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, ReservationId, UserId},
+    reservation::Reservation,
+    user::ReservationUser,
+};
+
+pub struct ReservationPositionRow {
+    pub reservation_id: ReservationId,
+    pub position: i64,
+}
+
+pub struct ReservationRow {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ReservationRow> for Reservation {
+    fn from(value: ReservationRow) -> Self {
+        let ReservationRow {
+            reservation_id,
+            book_id,
+            user_id,
+            user_name,
+            position,
+            created_at,
+        } = value;
+        Reservation {
+            reservation_id,
+            book_id,
+            reserved_by: ReservationUser {
+                id: user_id,
+                name: user_name,
+            },
+            position,
+            reserved_at: created_at,
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use kernel::model::{id::UserId, role::Role, user::User};
+use kernel::model::{
+    id::UserId,
+    role::Role,
+    user::{User, UserDetail},
+};
 use shared::error::AppError;
 use sqlx::types::chrono::{DateTime, Utc};
 use std::str::FromStr;
@@ -31,3 +35,34 @@ impl TryFrom<UserRow> for User {
         })
     }
 }
+
+pub struct UserDetailRow {
+    pub user_id: UserId,
+    pub name: String,
+    pub email: String,
+    pub role_name: String,
+    pub active_checkout_count: i64,
+}
+
+impl TryFrom<UserDetailRow> for UserDetail {
+    type Error = AppError;
+    fn try_from(value: UserDetailRow) -> Result<Self, Self::Error> {
+        let UserDetailRow {
+            user_id,
+            name,
+            email,
+            role_name,
+            active_checkout_count,
+        } = value;
+        Ok(UserDetail {
+            user: User {
+                id: user_id,
+                name,
+                email,
+                role: Role::from_str(role_name.as_str())
+                    .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            },
+            active_checkout_count,
+        })
+    }
+}
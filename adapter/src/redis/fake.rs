@@ -0,0 +1,87 @@
+// テスト用に、GET / SETEX / DEL だけに応答するインメモリの Redis サーバーを起動する。
+// キャッシュの読み書きや削除を、実際の Redis を用意せずに確かめるために使う
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use super::RedisClient;
+use shared::config::RedisConfig;
+
+type Store = Arc<Mutex<HashMap<String, String>>>;
+
+pub async fn start() -> anyhow::Result<RedisClient> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    let store = Store::default();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, store.clone()));
+        }
+    });
+    Ok(RedisClient::new(&RedisConfig {
+        host: "127.0.0.1".into(),
+        port,
+    })?)
+}
+
+async fn serve(stream: TcpStream, store: Store) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    while let Some(command) = read_command(&mut reader).await? {
+        let reply = match command
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            ["GET", key] => match store.lock().unwrap().get(*key) {
+                Some(value) => format!("${}\r\n{value}\r\n", value.len()),
+                None => "$-1\r\n".into(),
+            },
+            ["SETEX", key, _ttl, value] => {
+                store
+                    .lock()
+                    .unwrap()
+                    .insert(key.to_string(), value.to_string());
+                "+OK\r\n".into()
+            }
+            ["DEL", keys @ ..] => {
+                let mut store = store.lock().unwrap();
+                let deleted = keys.iter().filter(|k| store.remove(**k).is_some()).count();
+                format!(":{deleted}\r\n")
+            }
+            // 接続時に送られる CLIENT SETINFO などには、そのまま成功を返す
+            _ => "+OK\r\n".into(),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+// RESP の配列形式のコマンドを読む。接続が閉じられた場合は None を返す
+async fn read_command<R: AsyncBufReadExt + Unpin>(
+    reader: &mut R,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let count: usize = line.trim_end().trim_start_matches('*').parse()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).await?;
+        let len: usize = line.trim_end().trim_start_matches('$').parse()?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf).await?;
+        buf.truncate(len);
+        args.push(String::from_utf8(buf)?);
+    }
+    Ok(Some(args))
+}
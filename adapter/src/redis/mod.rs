@@ -1,3 +1,5 @@
+#[cfg(test)]
+pub(crate) mod fake;
 pub mod model;
 
 use self::model::{RedisKey, RedisValue};
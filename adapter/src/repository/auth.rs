@@ -14,6 +14,7 @@ use shared::error::{AppError, AppResult};
 
 use crate::{
     database::{
+        map_db_error,
         model::auth::{from, AuthorizationKey, AuthorizedUserId, UserItem},
         ConnectionPool,
     },
@@ -43,12 +44,14 @@ impl AuthRepository for AuthRepositoryImpl {
     async fn verify_user(&self, email: &str, password: &str) -> AppResult<UserId> {
         let user_item = sqlx::query_as!(
             UserItem,
-            r#"SELECT user_id,password_hash FROM users WHERE email = $1;"#,
+            r#"SELECT user_id,password_hash FROM users WHERE LOWER(email) = LOWER($1);"#,
             email
         )
-        .fetch_one(self.db.inner_ref())
+        .fetch_optional(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?
+        // 存在しないメールアドレスも、パスワード誤りと区別せずにログイン失敗とする
+        .ok_or(AppError::UnauthenticatedError)?;
 
         let valid = bcrypt::verify(password, &user_item.password_hash)?;
 
@@ -69,3 +72,51 @@ impl AuthRepository for AuthRepositoryImpl {
         self.kv.delete(&key).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kernel::{model::user::event::CreateUser, repository::user::UserRepository};
+    use shared::config::RedisConfig;
+
+    use super::*;
+    use crate::repository::user::UserRepositoryImpl;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_verify_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // verify_user は Redis に接続しないため、接続先は使われない
+        let kv = Arc::new(RedisClient::new(&RedisConfig {
+            host: "localhost".into(),
+            port: 6379,
+        })?);
+        let repo = AuthRepositoryImpl::new(ConnectionPool::new(pool.clone()), kv, 60);
+        let user = UserRepositoryImpl::new(ConnectionPool::new(pool))
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test.user@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+
+        let user_id = repo
+            .verify_user("test.user@example.com", "test_password")
+            .await?;
+        assert_eq!(user_id, user.id);
+        // メールアドレスの大文字・小文字は区別しない
+        let user_id = repo
+            .verify_user("Test.User@Example.com", "test_password")
+            .await?;
+        assert_eq!(user_id, user.id);
+
+        // パスワード誤りも、存在しないメールアドレスもログイン失敗になる
+        let res = repo
+            .verify_user("test.user@example.com", "wrong_password")
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+        let res = repo
+            .verify_user("nobody@example.com", "test_password")
+            .await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+
+        Ok(())
+    }
+}
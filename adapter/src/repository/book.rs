@@ -1,24 +1,51 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::model::book::{
-    event::{CreateBook, UpdateBook},
-    Book, BookListOptions, Checkout,
+    event::{CreateBook, PatchBook, UpdateBook},
+    Book, BookListOptions, BookSearchOptions, BookSortKey, Checkout, SortOrder, TagMatch,
 };
 use kernel::model::{
     id::{BookId, UserId},
-    {book::event::DeleteBook, list::PaginatedList},
+    {
+        book::event::DeleteBook,
+        list::{CursorList, PaginatedList},
+    },
 };
 use kernel::repository::book::BookRepository;
-use shared::error::{AppError, AppResult};
+use shared::{
+    config::SearchField,
+    error::{AppError, AppResult},
+};
 
-use crate::database::model::book::{BookCheckoutRow, BookRow, PaginatedBookRow};
-use crate::database::ConnectionPool;
+use crate::database::model::book::{
+    BookCacheKey, BookCheckoutRow, BookRow, BookTagsRow, PaginatedBookRow,
+};
+use crate::database::{ensure_affected, map_db_error, ConnectionPool};
+use crate::redis::RedisClient;
 
 #[derive(new)]
 pub struct BookRepositoryImpl {
     db: ConnectionPool,
+    // 設定した場合は find_by_id で取得した蔵書を Redis にキャッシュする
+    #[new(default)]
+    cache: Option<BookCache>,
+}
+
+struct BookCache {
+    kv: Arc<RedisClient>,
+    ttl: u64,
+}
+
+impl BookRepositoryImpl {
+    pub fn with_cache(mut self, kv: Arc<RedisClient>, ttl: u64) -> Self {
+        self.cache = Some(BookCache { kv, ttl });
+        self
+    }
 }
 
 #[async_trait]
@@ -37,70 +64,393 @@ impl BookRepository for BookRepositoryImpl {
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(|e| duplicate_isbn_or(e, Some(&event.isbn)))?;
 
         Ok(())
     }
 
-    async fn find_all(&self, option: BookListOptions) -> AppResult<PaginatedList<Book>> {
-        let BookListOptions { limit, offset } = option;
+    // 複数の蔵書を1回の INSERT でまとめて登録し、引数の順に蔵書 ID を返す。
+    // 1文で登録するため、いずれかの行で失敗した場合はどの蔵書も登録されない
+    async fn create_many(
+        &self,
+        events: Vec<CreateBook>,
+        user_id: UserId,
+    ) -> AppResult<Vec<BookId>> {
+        if events.is_empty() {
+            return Ok(vec![]);
+        }
 
-        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
-            PaginatedBookRow,
+        let columns = BookColumns::from(events);
+        sqlx::query!(
+            r#"
+            INSERT INTO books (book_id, title, author, isbn, description, user_id)
+            SELECT t.book_id, t.title, t.author, t.isbn, t.description, $6
+            FROM UNNEST($1::UUID[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::VARCHAR[])
+                AS t(book_id, title, author, isbn, description)
+            "#,
+            &columns.book_ids as _,
+            &columns.titles,
+            &columns.authors,
+            &columns.isbns,
+            &columns.descriptions as _,
+            user_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| duplicate_isbn_or(e, None))?;
+
+        Ok(columns.book_ids)
+    }
+
+    // create_many と同じく1回の INSERT でまとめて登録するが、ISBN が登録済みの行や
+    // 同じリクエスト内で重複する行は飛ばして残りを登録する。
+    // 引数の順に、登録できた行は蔵書 ID を、飛ばした行は None を返す
+    async fn create_many_partial(
+        &self,
+        events: Vec<CreateBook>,
+        user_id: UserId,
+    ) -> AppResult<Vec<Option<BookId>>> {
+        if events.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let columns = BookColumns::from(events);
+        let created: HashSet<BookId> = sqlx::query_scalar!(
             r#"
+            INSERT INTO books (book_id, title, author, isbn, description, user_id)
+            SELECT t.book_id, t.title, t.author, t.isbn, t.description, $6
+            FROM UNNEST($1::UUID[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::VARCHAR[])
+                AS t(book_id, title, author, isbn, description)
+            ON CONFLICT DO NOTHING
+            RETURNING book_id AS "book_id: BookId"
+            "#,
+            &columns.book_ids as _,
+            &columns.titles,
+            &columns.authors,
+            &columns.isbns,
+            &columns.descriptions as _,
+            user_id as _,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?
+        .into_iter()
+        .collect();
+
+        Ok(columns
+            .book_ids
+            .into_iter()
+            .map(|book_id| created.contains(&book_id).then_some(book_id))
+            .collect())
+    }
+
+    async fn find_all(&self, option: BookListOptions) -> AppResult<PaginatedList<Book>> {
+        let BookListOptions {
+            limit,
+            offset,
+            owned_by,
+            only_available,
+            created_after,
+            created_before,
+            mut tags,
+            tag_match,
+            sort,
+            order,
+            skip_total,
+        } = option;
+
+        // すべてのタグに一致するかはタグの件数で判定するため、重複を除いておく
+        tags.sort();
+        tags.dedup();
+        let match_all = tag_match == TagMatch::All;
+
+        // 並び替えの項目はユーザー入力の文字列を SQL に埋め込まず、
+        // 列挙型から決まる固定の値だけを渡して CASE 式で列を選ぶ
+        let sort = match sort {
+            BookSortKey::CreatedAt => "created_at",
+            BookSortKey::Title => "title",
+            BookSortKey::Author => "author",
+        };
+        let ascending = order == SortOrder::Asc;
+
+        // 総件数が不要な場合は、全件を走査する COUNT(*) OVER() を含まないクエリで取得する
+        let (total, book_ids) = if skip_total {
+            let book_ids = self
+                .db
+                .retry(|| {
+                    sqlx::query_scalar!(
+                        r#"
+            SELECT b.book_id AS "book_id: BookId"
+            FROM books AS b
+            WHERE b.deleted_at IS NULL
+            AND ($3::UUID IS NULL OR b.user_id = $3)
+            AND (
+                NOT $6
+                OR NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.book_id = b.book_id)
+            )
+            AND b.created_at BETWEEN COALESCE($7::TIMESTAMPTZ, '-infinity') AND COALESCE($8::TIMESTAMPTZ, 'infinity')
+            AND (
+                CARDINALITY($9::VARCHAR[]) = 0
+                OR b.book_id IN (
+                    SELECT bt.book_id
+                    FROM book_tags AS bt
+                    INNER JOIN tags AS t USING(tag_id)
+                    WHERE t.name = ANY($9)
+                    GROUP BY bt.book_id
+                    HAVING NOT $10 OR COUNT(*) = CARDINALITY($9)
+                )
+            )
+            ORDER BY
+                CASE WHEN $4 = 'title' AND $5 THEN b.title END ASC,
+                CASE WHEN $4 = 'title' AND NOT $5 THEN b.title END DESC,
+                CASE WHEN $4 = 'author' AND $5 THEN b.author END ASC,
+                CASE WHEN $4 = 'author' AND NOT $5 THEN b.author END DESC,
+                CASE WHEN $4 = 'created_at' AND $5 THEN b.created_at END ASC,
+                b.created_at DESC
+            LIMIT $1
+            OFFSET $2
+          "#,
+                        limit,
+                        offset,
+                        owned_by as _,
+                        sort,
+                        ascending,
+                        only_available,
+                        created_after,
+                        created_before,
+                        &tags,
+                        match_all,
+                    )
+                    .fetch_all(self.db.inner_ref())
+                })
+                .await
+                .map_err(map_db_error)?;
+            (None, book_ids)
+        } else {
+            let rows: Vec<PaginatedBookRow> = self
+                .db
+                .retry(|| {
+                    sqlx::query_as!(
+                        PaginatedBookRow,
+                        r#"
             SELECT
                 COUNT(*) OVER() as "total!",
                 b.book_id AS id
             FROM books AS b
-            ORDER BY b.created_at DESC
+            WHERE b.deleted_at IS NULL
+            AND ($3::UUID IS NULL OR b.user_id = $3)
+            AND (
+                NOT $6
+                OR NOT EXISTS (SELECT 1 FROM checkouts AS c WHERE c.book_id = b.book_id)
+            )
+            AND b.created_at BETWEEN COALESCE($7::TIMESTAMPTZ, '-infinity') AND COALESCE($8::TIMESTAMPTZ, 'infinity')
+            AND (
+                CARDINALITY($9::VARCHAR[]) = 0
+                OR b.book_id IN (
+                    SELECT bt.book_id
+                    FROM book_tags AS bt
+                    INNER JOIN tags AS t USING(tag_id)
+                    WHERE t.name = ANY($9)
+                    GROUP BY bt.book_id
+                    HAVING NOT $10 OR COUNT(*) = CARDINALITY($9)
+                )
+            )
+            ORDER BY
+                CASE WHEN $4 = 'title' AND $5 THEN b.title END ASC,
+                CASE WHEN $4 = 'title' AND NOT $5 THEN b.title END DESC,
+                CASE WHEN $4 = 'author' AND $5 THEN b.author END ASC,
+                CASE WHEN $4 = 'author' AND NOT $5 THEN b.author END DESC,
+                CASE WHEN $4 = 'created_at' AND $5 THEN b.created_at END ASC,
+                b.created_at DESC
             LIMIT $1
             OFFSET $2
           "#,
+                        limit,
+                        offset,
+                        owned_by as _,
+                        sort,
+                        ascending,
+                        only_available,
+                        created_after,
+                        created_before,
+                        &tags,
+                        match_all,
+                    )
+                    .fetch_all(self.db.inner_ref())
+                })
+                .await
+                .map_err(map_db_error)?;
+
+            let total = rows.first().map(|r| r.total).unwrap_or_default(); //レコードが一つもないときはtotalも0になる
+            (Some(total), rows.into_iter().map(|r| r.id).collect())
+        };
+        let items = self.find_by_ids(&book_ids).await?;
+
+        Ok(PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    // 登録日時の新しい順に、cursor の蔵書より後の蔵書を取得する。
+    // OFFSET と違い、深いページでも読み飛ばしが発生せず、途中で蔵書が追加されても重複・欠落しない
+    async fn find_all_after(
+        &self,
+        cursor: Option<BookId>,
+        limit: i64,
+    ) -> AppResult<CursorList<Book, BookId>> {
+        // 存在しない蔵書を cursor にすると条件が NULL になり、空のページを返してしまうため先に確かめる。
+        // 一覧の途中で削除された蔵書でも続きを取得できるよう、削除済みの蔵書は cursor として受け付ける
+        if let Some(cursor) = cursor {
+            let exists = sqlx::query_scalar!(
+                r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1) AS "exists!""#,
+                cursor as _
+            )
+            .fetch_one(self.db.inner_ref())
+            .await
+            .map_err(map_db_error)?;
+            if !exists {
+                return Err(AppError::invalid_field("cursor", "unknown cursor"));
+            }
+        }
+
+        // 続きがあるかを調べるため、1件多く取得する
+        let mut book_ids = sqlx::query_scalar!(
+            r#"
+            SELECT b.book_id AS "book_id: BookId"
+            FROM books AS b
+            WHERE b.deleted_at IS NULL
+            AND (
+                $1::UUID IS NULL
+                OR (b.created_at, b.book_id) < (
+                    SELECT c.created_at, c.book_id FROM books AS c WHERE c.book_id = $1
+                )
+            )
+            ORDER BY b.created_at DESC, b.book_id DESC
+            LIMIT $2
+            "#,
+            cursor as _,
+            limit + 1,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        let next_cursor = if book_ids.len() as i64 > limit {
+            book_ids.truncate(limit as usize);
+            book_ids.last().copied()
+        } else {
+            None
+        };
+        let items = self.find_by_ids(&book_ids).await?;
+
+        Ok(CursorList {
+            limit,
+            items,
+            next_cursor,
+        })
+    }
+
+    async fn find_favorites(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Book>> {
+        let BookListOptions { limit, offset, .. } = options;
+
+        // お気に入りに登録した日時の新しい順に並べる
+        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
+            PaginatedBookRow,
+            r#"
+            SELECT
+                COUNT(*) OVER() as "total!",
+                f.book_id AS id
+            FROM favorites AS f
+            INNER JOIN books AS b USING(book_id)
+            WHERE f.user_id = $1
+            AND b.deleted_at IS NULL
+            ORDER BY f.created_at DESC
+            LIMIT $2
+            OFFSET $3
+          "#,
+            user_id as _,
             limit,
             offset,
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        let total = rows.first().map(|r| r.total).unwrap_or_default(); //レコードが一つもないときはtotalも0になる
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
         let book_ids = rows.into_iter().map(|r| r.id).collect::<Vec<BookId>>();
+        let items = self.find_by_ids(&book_ids).await?;
+
+        Ok(PaginatedList {
+            total: Some(total),
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    async fn find_by_keyword(
+        &self,
+        search: BookSearchOptions,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Book>> {
+        let BookSearchOptions { terms, fields } = search;
+        // 検索語が空の場合は何も見つからないのではなく、通常の一覧と同じ結果を返す
+        if terms.is_empty() {
+            return self.find_all(options).await;
+        }
+        let BookListOptions { limit, offset, .. } = options;
+
+        // 各単語を前方一致させ、すべての単語を含むものを探す。
+        // 単語は API 層で英数字だけに絞り込まれているため、tsquery の演算子は含まれない
+        let query = terms
+            .iter()
+            .map(|term| format!("{term}:*"))
+            .collect::<Vec<_>>()
+            .join(" & ");
 
-        let rows: Vec<BookRow> = sqlx::query_as!(
-            BookRow,
+        // 検索対象の項目は SQL に埋め込まず、項目ごとのフラグとして渡す
+        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
+            PaginatedBookRow,
             r#"
             SELECT
-                b.book_id AS book_id,
-                b.title AS title,
-                b.author AS author,
-                b.isbn AS isbn,
-                b.description AS description,
-                u.user_id AS owned_by,
-                u.name AS owner_name
+                COUNT(*) OVER() as "total!",
+                b.book_id AS id
             FROM books AS b
-            INNER JOIN users AS u USING(user_id)
-            WHERE b.book_id IN (SELECT * FROM UNNEST($1::UUID[]))
+            WHERE b.deleted_at IS NULL
+            AND to_tsvector(
+                'simple',
+                CASE WHEN $1 THEN b.title ELSE '' END || ' ' ||
+                CASE WHEN $2 THEN b.author ELSE '' END || ' ' ||
+                CASE WHEN $3 THEN COALESCE(b.description, '') ELSE '' END
+            ) @@ to_tsquery('simple', $4)
             ORDER BY b.created_at DESC
-            "#,
-            &book_ids as _,
+            LIMIT $5
+            OFFSET $6
+          "#,
+            fields.contains(&SearchField::Title),
+            fields.contains(&SearchField::Author),
+            fields.contains(&SearchField::Description),
+            query,
+            limit,
+            offset,
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        let book_ids = rows.iter().map(|book| book.book_id).collect::<Vec<_>>();
-        let mut checkouts = self.find_checkouts(&book_ids).await?;
-
-        let items = rows
-            .into_iter()
-            .map(|row| {
-                let checkout = checkouts.remove(&row.book_id);
-                row.into_book(checkout)
-            })
-            .collect();
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let book_ids = rows.into_iter().map(|r| r.id).collect::<Vec<BookId>>();
+        let items = self.find_by_ids(&book_ids).await?;
 
         Ok(PaginatedList {
-            total,
+            total: Some(total),
             limit,
             offset,
             items,
@@ -108,9 +458,18 @@ impl BookRepository for BookRepositoryImpl {
     }
 
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>> {
-        let row: Option<BookRow> = sqlx::query_as!(
-            BookRow,
-            r#"
+        if let Some(row) = self.cached_book_row(book_id).await {
+            let checkout = self.find_checkouts(&[book_id]).await?.remove(&book_id);
+            let tags = self.find_tags(&[book_id]).await?.remove(&book_id);
+            return Ok(Some(row.into_book(checkout, tags.unwrap_or_default())));
+        }
+
+        let row: Option<BookRow> = self
+            .db
+            .retry(|| {
+                sqlx::query_as!(
+                    BookRow,
+                    r#"
         SELECT
             b.book_id AS book_id,
             b.title AS title,
@@ -118,26 +477,108 @@ impl BookRepository for BookRepositoryImpl {
             b.isbn AS isbn,
             b.description AS description,
             u.user_id AS owned_by,
-            u.name AS owner_name
+            u.name AS owner_name,
+            u.email AS owner_email,
+            b.version AS version,
+            b.created_at AS created_at,
+            b.updated_at AS updated_at
         FROM books AS b
         INNER JOIN users AS u USING(user_id)
         WHERE book_id = $1
+        AND b.deleted_at IS NULL
         "#,
-            book_id as _, //query_as!マクロによるコンパイル時の型チェックを無効化
-        )
-        .fetch_optional(self.db.inner_ref())
-        .await
-        .map_err(AppError::SpecificOperationError)?;
+                    book_id as _, //query_as!マクロによるコンパイル時の型チェックを無効化
+                )
+                .fetch_optional(self.db.inner_ref())
+            })
+            .await
+            .map_err(map_db_error)?;
 
         match row {
             Some(r) => {
+                self.store_book_row(&r).await;
                 let checkout = self.find_checkouts(&[r.book_id]).await?.remove(&r.book_id);
-                Ok(Some(r.into_book(checkout)))
+                let tags = self.find_tags(&[r.book_id]).await?.remove(&r.book_id);
+                Ok(Some(r.into_book(checkout, tags.unwrap_or_default())))
             }
             None => Ok(None),
         }
     }
 
+    // 指定した ID の蔵書を貸出状態とあわせて 1 回の問い合わせで取得する。
+    // 並び順は引数の ID の順に揃え、存在しない（削除済みの）ID は結果に含めない
+    async fn find_by_ids(&self, book_ids: &[BookId]) -> AppResult<Vec<Book>> {
+        let rows: Vec<BookRow> = self
+            .db
+            .retry(|| {
+                sqlx::query_as!(
+                    BookRow,
+                    r#"
+            SELECT
+                b.book_id AS book_id,
+                b.title AS title,
+                b.author AS author,
+                b.isbn AS isbn,
+                b.description AS description,
+                u.user_id AS owned_by,
+                u.name AS owner_name,
+                u.email AS owner_email,
+                b.version AS version,
+                b.created_at AS created_at,
+                b.updated_at AS updated_at
+            FROM books AS b
+            -- books.user_id は NOT NULL で、所有者を削除すると蔵書も ON DELETE CASCADE で削除される。
+            -- そのため所有者のいない蔵書はなく、INNER JOIN で一覧から漏れる蔵書もない
+            INNER JOIN users AS u USING(user_id)
+            WHERE b.book_id IN (SELECT * FROM UNNEST($1::UUID[]))
+            AND b.deleted_at IS NULL
+            "#,
+                    book_ids as _,
+                )
+                .fetch_all(self.db.inner_ref())
+            })
+            .await
+            .map_err(map_db_error)?;
+
+        let mut checkouts = self.find_checkouts(book_ids).await?;
+        let mut tags = self.find_tags(book_ids).await?;
+        let mut rows: HashMap<BookId, BookRow> =
+            rows.into_iter().map(|row| (row.book_id, row)).collect();
+
+        Ok(book_ids
+            .iter()
+            .filter_map(|book_id| rows.remove(book_id))
+            .map(|row| {
+                let checkout = checkouts.remove(&row.book_id);
+                let tags = tags.remove(&row.book_id).unwrap_or_default();
+                row.into_book(checkout, tags)
+            })
+            .collect())
+    }
+
+    // ISBN はハイフンの有無を問わずに探す（978-4-79-806170-2 と 9784798061702 は同じ蔵書）
+    async fn find_by_isbn(&self, isbn: &str) -> AppResult<Option<Book>> {
+        let isbn = isbn.trim().replace('-', "");
+        // 一意インデックス books_isbn_key と同じ式・条件で絞り込み、インデックスを使って引く
+        let book_id = sqlx::query_scalar!(
+            r#"
+            SELECT b.book_id AS "book_id: BookId"
+            FROM books AS b
+            WHERE REPLACE(b.isbn, '-', '') = $1
+            AND b.deleted_at IS NULL
+            "#,
+            isbn,
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        match book_id {
+            Some(book_id) => self.find_by_id(book_id).await,
+            None => Ok(None),
+        }
+    }
+
     async fn update(&self, event: UpdateBook) -> AppResult<()> {
         let res = sqlx::query!(
             r#"
@@ -146,9 +587,12 @@ impl BookRepository for BookRepositoryImpl {
             title = $1,
             author = $2,
             isbn = $3,
-            description = $4
+            description = $4,
+            version = version + 1
         WHERE book_id = $5
         AND user_id = $6
+        AND deleted_at IS NULL
+        AND version = $7
         "#,
             event.title,
             event.author,
@@ -156,39 +600,211 @@ impl BookRepository for BookRepositoryImpl {
             event.description,
             event.book_id as _,
             event.requested_user as _,
+            event.version,
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(|e| duplicate_isbn_or(e, Some(&event.isbn)))?;
+
+        if res.rows_affected() > 0 {
+            self.invalidate_book_cache(event.book_id).await;
+            return Ok(());
+        }
+
+        // 更新できなかった理由が、蔵書がないことか版の不一致かを区別する
+        let current_version = sqlx::query_scalar!(
+            r#"
+        SELECT version FROM books
+        WHERE book_id = $1
+        AND user_id = $2
+        AND deleted_at IS NULL
+        "#,
+            event.book_id as _,
+            event.requested_user as _,
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("Specified boook not found".into()));
+        match current_version {
+            Some(current) => Err(AppError::ConflictingUpdate(format!(
+                "蔵書（{}）は他のユーザーによって更新されています（現在の版: {}, 指定された版: {}）。",
+                event.book_id, current, event.version
+            ))),
+            None => Err(AppError::not_found("Book", event.book_id)),
         }
+    }
+
+    async fn patch(&self, event: PatchBook) -> AppResult<()> {
+        // description は「指定なし」と「NULL に更新」を区別するため、
+        // 指定の有無を表すフラグと値を別々に渡す
+        let (set_description, description) = match event.description {
+            Some(description) => (true, description),
+            None => (false, None),
+        };
+
+        let res = sqlx::query!(
+            r#"
+        UPDATE books
+        SET
+            title = COALESCE($1, title),
+            author = COALESCE($2, author),
+            isbn = COALESCE($3, isbn),
+            description = CASE WHEN $4 THEN $5 ELSE description END,
+            version = version + 1
+        WHERE book_id = $6
+        AND user_id = $7
+        AND deleted_at IS NULL
+        "#,
+            event.title,
+            event.author,
+            event.isbn,
+            set_description,
+            description,
+            event.book_id as _,
+            event.requested_user as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| duplicate_isbn_or(e, event.isbn.as_deref()))?;
+
+        ensure_affected(&res, AppError::not_found("Book", event.book_id))?;
+        self.invalidate_book_cache(event.book_id).await;
         Ok(())
     }
 
+    // 蔵書を削除できるのは、所有者か管理者だけ。
+    // 貸出履歴を残すため行は消さず、削除日時を記録して一覧や検索から除外する
     async fn delete(&self, event: DeleteBook) -> AppResult<()> {
         let res = sqlx::query!(
             r#"
-        DELETE FROM books
+        UPDATE books
+        SET deleted_at = CURRENT_TIMESTAMP(3)
         WHERE book_id = $1
-        AND user_id = $2
+        AND deleted_at IS NULL
+        AND (
+            user_id = $2
+            OR EXISTS (
+                SELECT 1 FROM users AS u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE u.user_id = $2 AND r.name = 'Admin'
+            )
+        )
         "#,
             event.book_id as _,
             event.requested_user as _,
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound("Specified book not found".into()));
-        }
+        ensure_affected(&res, AppError::not_found("Book", event.book_id))?;
+        self.invalidate_book_cache(event.book_id).await;
         Ok(())
     }
+
+    // 削除済みの蔵書を元に戻す。管理者だけが使う想定で、権限の確認は呼び出し側で行う
+    async fn restore(&self, book_id: BookId) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+        UPDATE books
+        SET deleted_at = NULL
+        WHERE book_id = $1
+        AND deleted_at IS NOT NULL
+        "#,
+            book_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(|e| duplicate_isbn_or(e, None))?;
+
+        ensure_affected(&res, AppError::not_found("Deleted book", book_id))
+    }
+}
+
+// UNNEST でまとめて INSERT できるよう、登録する蔵書を列ごとの配列に分ける。
+// RETURNING の並び順は保証されないため、ID はここで採番して渡す
+struct BookColumns {
+    book_ids: Vec<BookId>,
+    titles: Vec<String>,
+    authors: Vec<String>,
+    isbns: Vec<String>,
+    descriptions: Vec<Option<String>>,
+}
+
+impl From<Vec<CreateBook>> for BookColumns {
+    fn from(events: Vec<CreateBook>) -> Self {
+        let mut columns = Self {
+            book_ids: Vec::with_capacity(events.len()),
+            titles: Vec::with_capacity(events.len()),
+            authors: Vec::with_capacity(events.len()),
+            isbns: Vec::with_capacity(events.len()),
+            descriptions: Vec::with_capacity(events.len()),
+        };
+        for event in events {
+            columns.book_ids.push(BookId::new());
+            columns.titles.push(event.title);
+            columns.authors.push(event.author);
+            columns.isbns.push(event.isbn);
+            columns.descriptions.push(event.description);
+        }
+        columns
+    }
+}
+
+// isbn には（削除済みの蔵書を除いて）ハイフンを除いた値での一意制約があるため、
+// 登録済みの ISBN での登録・更新は一意制約違反になる
+fn duplicate_isbn_or(e: sqlx::Error, isbn: Option<&str>) -> AppError {
+    match e.as_database_error() {
+        Some(db) if db.is_unique_violation() => AppError::DuplicateIsbn(match isbn {
+            Some(isbn) => format!("ISBN（{isbn}）の蔵書は既に登録されています。"),
+            None => "同じ ISBN の蔵書が既に登録されています。".into(),
+        }),
+        _ => map_db_error(e),
+    }
+}
+
+// 更新・削除の後に呼ぶ。削除できなかった場合も、古い内容は TTL が切れるまでに消える。
+// ユーザーの削除に伴って蔵書が消える場合にも使う
+pub(crate) async fn invalidate_book_cache(kv: &RedisClient, book_id: BookId) {
+    if let Err(e) = kv.delete(&BookCacheKey(book_id)).await {
+        tracing::warn!(error.message = %e, "Failed to invalidate the book cache");
+    }
 }
 
 impl BookRepositoryImpl {
+    // キャッシュが未設定の場合や Redis から読めない場合は、データベースから取得させるため None を返す
+    async fn cached_book_row(&self, book_id: BookId) -> Option<BookRow> {
+        let cache = self.cache.as_ref()?;
+        match cache.kv.get(&BookCacheKey(book_id)).await {
+            Ok(row) => row,
+            Err(e) => {
+                tracing::warn!(error.message = %e, "Failed to read book from the cache");
+                None
+            }
+        }
+    }
+
+    // キャッシュへの書き込みに失敗しても、取得自体は成功として扱う
+    async fn store_book_row(&self, row: &BookRow) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        if let Err(e) = cache
+            .kv
+            .set_ex(&BookCacheKey(row.book_id), row, cache.ttl)
+            .await
+        {
+            tracing::warn!(error.message = %e, "Failed to write book to the cache");
+        }
+    }
+
+    async fn invalidate_book_cache(&self, book_id: BookId) {
+        if let Some(cache) = &self.cache {
+            invalidate_book_cache(&cache.kv, book_id).await;
+        }
+    }
+
     async fn find_checkouts(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Checkout>> {
         let res = sqlx::query_as!(
             BookCheckoutRow,
@@ -208,11 +824,37 @@ impl BookRepositoryImpl {
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
+        .map_err(map_db_error)?
         .into_iter()
         .map(|checkout| (checkout.book_id, Checkout::from(checkout)))
         .collect();
-    
+
+        Ok(res)
+    }
+
+    // 蔵書ごとのタグを名前の昇順にまとめて取得する。タグのない蔵書は結果に含めない
+    async fn find_tags(&self, book_ids: &[BookId]) -> AppResult<HashMap<BookId, Vec<String>>> {
+        let res = sqlx::query_as!(
+            BookTagsRow,
+            r#"
+            SELECT
+                bt.book_id AS "book_id: BookId",
+                ARRAY_AGG(t.name ORDER BY t.name) AS "tags!"
+            FROM book_tags AS bt
+            INNER JOIN tags AS t USING(tag_id)
+            WHERE bt.book_id = ANY($1)
+            GROUP BY bt.book_id
+            ;
+            "#,
+            book_ids as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?
+        .into_iter()
+        .map(|row| (row.book_id, row.tags))
+        .collect();
+
         Ok(res)
     }
 }
@@ -220,18 +862,13 @@ impl BookRepositoryImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::repository::{
-        book::BookRepositoryImpl,
-        user::UserRepositoryImpl, //checkout::CheckoutRepositoryImpl
-    };
-    // use chrono::Utc;
+    use crate::repository::{book::BookRepositoryImpl, user::UserRepositoryImpl};
     use kernel::{
         model::{
-            checkout::event::{CreateCheckout, UpdateReturned},
             id::UserId,
-            user::event::CreateUser,
+            user::event::{CreateUser, DeleteUser},
         },
-        repository::{checkout::CheckoutRepository, user::UserRepository},
+        repository::user::UserRepository,
     };
     use std::str::FromStr;
 
@@ -257,13 +894,14 @@ mod tests {
             title: "Test Title".into(),
             author: "Test Author".into(),
             isbn: "Test ISBN".into(),
-            description: "Test Description".into(),
+            description: Some("Test Description".into()),
         };
         repo.create(book, user.id).await?;
         // find_all を実行するためには BookListOptions 型の値が必要なので作る。
         let options = BookListOptions {
             limit: 20,
             offset: 0,
+            ..Default::default()
         };
         let res = repo.find_all(options).await?;
         assert_eq!(res.items.len(), 1);
@@ -283,34 +921,321 @@ mod tests {
         assert_eq!(title, "Test Title");
         assert_eq!(author, "Test Author");
         assert_eq!(isbn, "Test ISBN");
-        assert_eq!(description, "Test Description");
+        assert_eq!(description.as_deref(), Some("Test Description"));
         assert_eq!(owner.name, "Test User");
         Ok(())
     }
 
-    #[sqlx::test(fixtures("common", "book"))]
-    async fn test_update_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_without_description(pool: sqlx::PgPool) -> anyhow::Result<()> {
         let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
-        // 2. fixtures/book.sql で作成済みの書籍を取得
-        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b").unwrap();
-        let book = repo.find_by_id(book_id).await?.unwrap();
-        const NEW_AUTHOR: &str = "更新後の著者名";
-        assert_ne!(book.author, NEW_AUTHOR);
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book = |isbn: &str| CreateBook {
+            title: "Test Title".into(),
+            author: "Test Author".into(),
+            isbn: isbn.into(),
+            description: None,
+        };
 
-        // 3. 書籍の更新用のパラメータを作成し、更新を行う
-        let update_book = UpdateBook {
-            book_id: book.id,
-            title: book.title,
+        // 1件ずつ登録しても、まとめて登録しても説明は NULL になる
+        repo.create(book("ISBN-1"), user_id).await?;
+        let book_ids = repo.create_many(vec![book("ISBN-2")], user_id).await?;
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                offset: 0,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.items.len(), 2);
+        assert!(res.items.iter().all(|book| book.description.is_none()));
+        let book = repo.find_by_id(book_ids[0]).await?.unwrap();
+        assert_eq!(book.description, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_many(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book = |title: &str| CreateBook {
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: format!("{title} ISBN"),
+            description: Some("Test Description".into()),
+        };
+
+        assert!(repo.create_many(vec![], user_id).await?.is_empty());
+
+        let book_ids = repo
+            .create_many(vec![book("First"), book("Second")], user_id)
+            .await?;
+        assert_eq!(book_ids.len(), 2);
+        assert_eq!(repo.find_by_id(book_ids[0]).await?.unwrap().title, "First");
+        assert_eq!(repo.find_by_id(book_ids[1]).await?.unwrap().title, "Second");
+
+        // 1件でも登録できない行があれば、どの蔵書も登録されない
+        let res = repo
+            .create_many(vec![book("Third"), book(&"x".repeat(256))], user_id)
+            .await;
+        assert!(res.is_err());
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                offset: 0,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, Some(2));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_create_many_partial(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book = |title: &str, isbn: &str| CreateBook {
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: isbn.into(),
+            description: None,
+        };
+
+        // fixtures/book.sql の蔵書と同じ ISBN の行と、リクエスト内で重複する行は飛ばして登録する
+        let book_ids = repo
+            .create_many_partial(
+                vec![
+                    book("First", "First ISBN"),
+                    book("Duplicate", "9784798061702"),
+                    book("Second", "Second ISBN"),
+                    book("Repeated", "First ISBN"),
+                ],
+                user_id,
+            )
+            .await?;
+        assert_eq!(book_ids.len(), 4);
+        assert!(book_ids[1].is_none());
+        assert!(book_ids[3].is_none());
+        let first = repo.find_by_id(book_ids[0].unwrap()).await?.unwrap();
+        assert_eq!(first.title, "First");
+        let second = repo.find_by_id(book_ids[2].unwrap()).await?.unwrap();
+        assert_eq!(second.title, "Second");
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_delete_owner(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let user = user_repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        let book = |title: &str| CreateBook {
+            title: title.into(),
+            author: "Test Author".into(),
+            isbn: format!("{title} ISBN"),
+            description: Some("Test Description".into()),
+        };
+        let owned = repo.create_many(vec![book("Owned")], user.id).await?;
+        let kept = repo.create_many(vec![book("Kept")], admin_id).await?;
+        let options = || BookListOptions {
+            limit: 20,
+            offset: 0,
+            ..Default::default()
+        };
+        assert_eq!(repo.find_all(options()).await?.total, Some(2));
+
+        // 所有者を削除すると蔵書も削除され、一覧の件数と中身が食い違うことはない
+        user_repo.delete(DeleteUser { user_id: user.id }).await?;
+        let res = repo.find_all(options()).await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].id, kept[0]);
+        assert!(repo.find_by_id(owned[0]).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_duplicate_isbn(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        // fixtures/book.sql の蔵書と同じ ISBN
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let book = || CreateBook {
+            title: "Another Title".into(),
+            author: "Another Author".into(),
+            isbn: "978-4798061702".into(),
+            description: None,
+        };
+
+        let res = repo.create(book(), user_id).await;
+        assert!(matches!(res, Err(AppError::DuplicateIsbn(_))));
+        let res = repo.create_many(vec![book()], user_id).await;
+        assert!(matches!(res, Err(AppError::DuplicateIsbn(_))));
+        // ハイフンの有無だけが異なる ISBN も重複として扱う
+        let res = repo
+            .create(
+                CreateBook {
+                    isbn: "9784798061702".into(),
+                    ..book()
+                },
+                user_id,
+            )
+            .await;
+        assert!(matches!(res, Err(AppError::DuplicateIsbn(_))));
+
+        // 削除済みの蔵書の ISBN では登録できるが、その後に元の蔵書は復元できない
+        repo.delete(DeleteBook {
+            book_id,
+            requested_user: user_id,
+        })
+        .await?;
+        repo.create(book(), user_id).await?;
+        let res = repo.restore(book_id).await;
+        assert!(matches!(res, Err(AppError::DuplicateIsbn(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_find_by_isbn(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        // fixtures/book.sql の蔵書の ISBN は 978-4798061702
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        for isbn in ["978-4798061702", "9784798061702", "978-4-79-806170-2"] {
+            let book = repo.find_by_isbn(isbn).await?;
+            assert_eq!(book.map(|b| b.id), Some(book_id));
+        }
+        assert!(repo.find_by_isbn("9784798061703").await?.is_none());
+
+        // 削除済みの蔵書は見つからない
+        repo.delete(DeleteBook {
+            book_id,
+            requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?,
+        })
+        .await?;
+        assert!(repo.find_by_isbn("9784798061702").await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_update_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        // 2. fixtures/book.sql で作成済みの書籍を取得
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b").unwrap();
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        const NEW_AUTHOR: &str = "更新後の著者名";
+        assert_ne!(book.author, NEW_AUTHOR);
+
+        // 3. 書籍の更新用のパラメータを作成し、更新を行う
+        let update_book = UpdateBook {
+            book_id: book.id,
+            title: book.title,
             author: NEW_AUTHOR.into(), // ここが差分
             isbn: book.isbn,
             description: book.description,
             requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
+            version: book.version,
         };
         repo.update(update_book).await.unwrap();
 
         // 4. 更新後の書籍を取得し、期待通りに更新されていることを検証する
         let book = repo.find_by_id(book_id).await?.unwrap();
         assert_eq!(book.author, NEW_AUTHOR);
+        assert_eq!(book.version, 2);
+
+        // 古い版を指定した更新は ConflictingUpdate（409）になり、内容は変わらない
+        let res = repo
+            .update(UpdateBook {
+                book_id,
+                title: "Stale Title".into(),
+                author: book.author.clone(),
+                isbn: book.isbn.clone(),
+                description: None,
+                requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
+                version: 1,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::ConflictingUpdate(_))));
+        assert_ne!(
+            repo.find_by_id(book_id).await?.unwrap().title,
+            "Stale Title"
+        );
+
+        // 5. 存在しない書籍の更新は EntityNotFound（404）になり、メッセージに ID を含む
+        let missing_id = BookId::new();
+        let res = repo
+            .update(UpdateBook {
+                book_id: missing_id,
+                title: book.title,
+                author: book.author,
+                isbn: book.isbn,
+                description: None,
+                requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
+                version: 1,
+            })
+            .await;
+        assert!(matches!(
+            res,
+            Err(AppError::EntityNotFound(msg)) if msg == format!("Book with id {missing_id} not found")
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_patch_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let requested_user = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let original = repo.find_by_id(book_id).await?.unwrap();
+        let patch = |title: Option<&str>, description: Option<Option<&str>>| PatchBook {
+            book_id,
+            title: title.map(String::from),
+            author: None,
+            isbn: None,
+            description: description.map(|d| d.map(String::from)),
+            requested_user,
+        };
+
+        // 指定なしの項目は変更されない
+        repo.patch(patch(Some("新しいタイトル"), None)).await?;
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "新しいタイトル");
+        assert_eq!(book.author, original.author);
+        assert_eq!(book.description, original.description);
+
+        // 値を指定すると更新される
+        repo.patch(patch(None, Some(Some("新しい説明")))).await?;
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.title, "新しいタイトル");
+        assert_eq!(book.description.as_deref(), Some("新しい説明"));
+
+        // null を指定すると消去される
+        repo.patch(patch(None, Some(None))).await?;
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.description, None);
+
+        // 所有者以外は更新できない
+        let res = repo
+            .patch(PatchBook {
+                requested_user: UserId::new(),
+                ..patch(Some("他人による更新"), None)
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
 
         Ok(())
     }
@@ -332,6 +1257,113 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_delete_book_permission(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        // fixtures/book.sql の蔵書は管理者の所有
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let admin_book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        let other = user_repo
+            .create(CreateUser {
+                name: "Other User".into(),
+                email: "other@example.com".into(),
+                password: "other_password".into(),
+            })
+            .await?;
+
+        // 一般ユーザーは他人の蔵書を削除できない
+        let res = repo
+            .delete(DeleteBook {
+                book_id: admin_book_id,
+                requested_user: other.id,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(repo.find_by_id(admin_book_id).await?.is_some());
+
+        // 管理者は他人の蔵書も削除できる
+        repo.create(
+            CreateBook {
+                title: "Other Title".into(),
+                author: "Other Author".into(),
+                isbn: "Other ISBN".into(),
+                description: None,
+            },
+            other.id,
+        )
+        .await?;
+        let other_book_id = repo
+            .find_all(BookListOptions {
+                limit: 1,
+                offset: 0,
+                owned_by: Some(other.id),
+                ..Default::default()
+            })
+            .await?
+            .items[0]
+            .id;
+        repo.delete(DeleteBook {
+            book_id: other_book_id,
+            requested_user: admin_id,
+        })
+        .await?;
+        assert!(repo.find_by_id(other_book_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_restore_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let requested_user = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let options = || BookListOptions {
+            limit: 20,
+            offset: 0,
+            ..Default::default()
+        };
+
+        // 削除されていない蔵書は復元できない
+        let res = repo.restore(book_id).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 削除した蔵書は一覧・取得・更新の対象外になり、二重に削除することもできない
+        repo.delete(DeleteBook {
+            book_id,
+            requested_user,
+        })
+        .await?;
+        assert_eq!(repo.find_all(options()).await?.total, Some(0));
+        let res = repo
+            .delete(DeleteBook {
+                book_id,
+                requested_user,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let res = repo
+            .update(UpdateBook {
+                book_id,
+                title: "Updated Title".into(),
+                author: "Updated Author".into(),
+                isbn: "Updated ISBN".into(),
+                description: Some("Updated Description".into()),
+                requested_user,
+                version: 1,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 復元すると再び一覧に含まれる
+        repo.restore(book_id).await?;
+        assert!(repo.find_by_id(book_id).await?.is_some());
+        assert_eq!(repo.find_all(options()).await?.total, Some(1));
+
+        Ok(())
+    }
+
     #[sqlx::test(fixtures("common", "book_list"))]
     async fn test_list_filters(pool: sqlx::PgPool) -> anyhow::Result<()> {
         let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
@@ -342,9 +1374,10 @@ mod tests {
             .find_all(BookListOptions {
                 limit: 10,
                 offset: 0,
+                ..Default::default()
             })
             .await?;
-        assert_eq!(res.total, LEN);
+        assert_eq!(res.total, Some(LEN));
         assert_eq!(res.limit, 10);
         assert_eq!(res.offset, 0);
         assert_eq!(res.items[0].title, "title050");
@@ -353,9 +1386,10 @@ mod tests {
             .find_all(BookListOptions {
                 limit: 10,
                 offset: 10,
+                ..Default::default()
             })
             .await?;
-        assert_eq!(res.total, LEN);
+        assert_eq!(res.total, Some(LEN));
         assert_eq!(res.limit, 10);
         assert_eq!(res.offset, 10);
         assert_eq!(res.items[0].title, "title040");
@@ -364,13 +1398,398 @@ mod tests {
             .find_all(BookListOptions {
                 limit: 10,
                 offset: 100,
+                ..Default::default()
             })
             .await?;
-        assert_eq!(res.total, 0); // offsetがtotalを超える場合は0になる
+        assert_eq!(res.total, Some(0)); // offsetがtotalを超える場合は0になる
         assert_eq!(res.limit, 10);
         assert_eq!(res.offset, 100);
         assert_eq!(res.items.len(), 0);
 
+        // 総件数を省略しても、同じページの蔵書を返す
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 10,
+                offset: 10,
+                skip_total: true,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, None);
+        assert_eq!(res.items.len(), 10);
+        assert_eq!(res.items[0].title, "title040");
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_find_by_keyword_fields(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        repo.create(
+            CreateBook {
+                title: "Programming Rust".into(),
+                author: "Jim Blandy".into(),
+                isbn: "978-1-4920-5259-3".into(),
+                description: Some("Fast, safe systems development".into()),
+            },
+            owner_id,
+        )
+        .await?;
+
+        let search = |terms: &[&str], fields: &[SearchField]| BookSearchOptions {
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+            fields: fields.to_vec(),
+        };
+        let options = || BookListOptions {
+            limit: 20,
+            offset: 0,
+            ..Default::default()
+        };
+
+        // タイトルのみを対象にした場合、説明文にだけ含まれる単語では見つからない
+        let res = repo
+            .find_by_keyword(search(&["systems"], &[SearchField::Title]), options())
+            .await?;
+        assert_eq!(res.total, Some(0));
+        let res = repo
+            .find_by_keyword(search(&["rust"], &[SearchField::Title]), options())
+            .await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(res.items[0].title, "Programming Rust");
+
+        // すべての項目を対象にした場合は説明文や著者名でも見つかる。単語は前方一致する
+        let res = repo
+            .find_by_keyword(search(&["systems"], &SearchField::ALL), options())
+            .await?;
+        assert_eq!(res.total, Some(1));
+        let res = repo
+            .find_by_keyword(search(&["bland", "prog"], &SearchField::ALL), options())
+            .await?;
+        assert_eq!(res.total, Some(1));
+
+        // すべての単語を含む蔵書だけが見つかる
+        let res = repo
+            .find_by_keyword(search(&["rust", "python"], &SearchField::ALL), options())
+            .await?;
+        assert_eq!(res.total, Some(0));
+
+        // 検索語が空の場合は全件の一覧になる
+        let res = repo
+            .find_by_keyword(search(&[], &SearchField::ALL), options())
+            .await?;
+        assert_eq!(res.total, repo.find_all(options()).await?.total);
+        assert_eq!(res.total, Some(2));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_find_all_after(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // fixtures/book_list.sql の50冊を、新しい順に20冊ずつたどる
+        let mut titles = vec![];
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let res = repo.find_all_after(cursor, 20).await?;
+            titles.extend(res.items.into_iter().map(|book| book.title));
+            pages += 1;
+            match res.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(pages, 3);
+        let expected: Vec<String> = (1..=50).rev().map(|i| format!("title{i:03}")).collect();
+        assert_eq!(titles, expected);
+
+        // ちょうど最後の1冊で終わるページには続きがない
+        let res = repo.find_all_after(None, 50).await?;
+        assert_eq!(res.items.len(), 50);
+        assert!(res.next_cursor.is_none());
+
+        // 存在しない蔵書を cursor にすると、空のページではなくエラーを返す
+        let res = repo.find_all_after(Some(BookId::new()), 20).await;
+        assert!(matches!(res, Err(AppError::ValidationError(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_list_sort(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        // fixtures/book_list.sql の蔵書より後に、タイトルと著者名の並びが逆になる蔵書を登録する
+        repo.create(
+            CreateBook {
+                title: "aaa".into(),
+                author: "zzz".into(),
+                isbn: "isbn999".into(),
+                description: None,
+            },
+            UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?,
+        )
+        .await?;
+
+        let first_title = |sort, order| {
+            let repo = &repo;
+            async move {
+                let res = repo
+                    .find_all(BookListOptions {
+                        limit: 1,
+                        offset: 0,
+                        sort,
+                        order,
+                        ..Default::default()
+                    })
+                    .await?;
+                anyhow::Ok(res.items[0].title.clone())
+            }
+        };
+
+        use BookSortKey::*;
+        use SortOrder::*;
+        assert_eq!(first_title(CreatedAt, Desc).await?, "aaa");
+        assert_eq!(first_title(CreatedAt, Asc).await?, "title001");
+        assert_eq!(first_title(Title, Asc).await?, "aaa");
+        assert_eq!(first_title(Title, Desc).await?, "title050");
+        assert_eq!(first_title(Author, Asc).await?, "title001");
+        assert_eq!(first_title(Author, Desc).await?, "aaa");
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_list_owned_by(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // fixtures/book.sql の蔵書とは別の所有者の蔵書を2冊登録する
+        let other = user_repo
+            .create(CreateUser {
+                name: "Other User".into(),
+                email: "other@example.com".into(),
+                password: "other_password".into(),
+            })
+            .await?;
+        for title in ["Other Title 1", "Other Title 2"] {
+            repo.create(
+                CreateBook {
+                    title: title.into(),
+                    author: "Other Author".into(),
+                    isbn: format!("{title} ISBN"),
+                    description: Some("Other Description".into()),
+                },
+                other.id,
+            )
+            .await?;
+        }
+
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                offset: 0,
+                owned_by: Some(owner_id),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, Some(1));
+        assert!(res.items.iter().all(|b| b.owner.id == owner_id));
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 1,
+                offset: 0,
+                owned_by: Some(other.id),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].owner.id, other.id);
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 20,
+                offset: 0,
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(res.total, Some(3));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_list_created_range(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        // fixtures/book_list.sql の蔵書は 2024-01-01 00:00 の i 分後に登録されている
+        let at = |minutes: i64| {
+            "2024-01-01T00:00:00Z"
+                .parse::<chrono::DateTime<chrono::Utc>>()
+                .unwrap()
+                + chrono::Duration::minutes(minutes)
+        };
+        let options = |created_after, created_before| BookListOptions {
+            limit: 20,
+            offset: 0,
+            created_after,
+            created_before,
+            ..Default::default()
+        };
+
+        // 範囲の両端を含む
+        let res = repo.find_all(options(Some(at(10)), Some(at(12)))).await?;
+        assert_eq!(res.total, Some(3));
+        let titles: Vec<_> = res.items.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["title012", "title011", "title010"]);
+
+        // 片方だけの指定もできる
+        assert_eq!(
+            repo.find_all(options(Some(at(41)), None)).await?.total,
+            Some(10)
+        );
+        assert_eq!(
+            repo.find_all(options(None, Some(at(5)))).await?.total,
+            Some(5)
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_list_available(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let options = |only_available: bool| BookListOptions {
+            limit: 10,
+            offset: 0,
+            only_available,
+            ..Default::default()
+        };
+
+        // 登録の新しい2冊（title050, title049）を貸出中にする
+        sqlx::query!(
+            r#"
+            INSERT INTO checkouts (book_id, user_id, due_at)
+            SELECT book_id, user_id, CURRENT_TIMESTAMP(3)
+            FROM books
+            WHERE title IN ('title050', 'title049')
+            "#
+        )
+        .execute(&pool)
+        .await?;
+
+        let res = repo.find_all(options(false)).await?;
+        assert_eq!(res.total, Some(50));
+        assert_eq!(res.items[0].title, "title050");
+
+        // total も絞り込み後の件数になる
+        let res = repo.find_all(options(true)).await?;
+        assert_eq!(res.total, Some(48));
+        assert_eq!(res.items.len(), 10);
+        assert_eq!(res.items[0].title, "title048");
+        assert!(res.items.iter().all(|b| b.checkout.is_none()));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_find_by_ids(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool));
+        let books = repo
+            .find_all(BookListOptions {
+                limit: 3,
+                offset: 0,
+                ..Default::default()
+            })
+            .await?
+            .items;
+
+        // 引数の順に返し、存在しない ID は飛ばす
+        let ids = vec![books[2].id, BookId::new(), books[0].id, books[1].id];
+        let res = repo.find_by_ids(&ids).await?;
+        assert_eq!(
+            res.iter().map(|b| b.id).collect::<Vec<_>>(),
+            vec![books[2].id, books[0].id, books[1].id]
+        );
+
+        assert!(repo.find_by_ids(&[]).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_find_by_id_without_redis(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 接続できない Redis をキャッシュに指定しても、データベースから取得できる
+        let port = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await?
+            .local_addr()?
+            .port();
+        let kv = Arc::new(RedisClient::new(&shared::config::RedisConfig {
+            host: "127.0.0.1".into(),
+            port,
+        })?);
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool)).with_cache(kv, 60);
+
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.id, book_id);
+
+        // 更新・削除もキャッシュの削除に失敗したことでは失敗しない
+        repo.patch(PatchBook {
+            book_id,
+            title: Some("Cached Title".into()),
+            author: None,
+            isbn: None,
+            description: None,
+            requested_user: book.owner.id,
+        })
+        .await?;
+        assert_eq!(
+            repo.find_by_id(book_id).await?.unwrap().title,
+            "Cached Title"
+        );
+
+        repo.delete(DeleteBook {
+            book_id,
+            requested_user: book.owner.id,
+        })
+        .await?;
+        assert!(repo.find_by_id(book_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_book_cache_value() -> anyhow::Result<()> {
+        use crate::redis::model::{RedisKey, RedisValue};
+
+        let book_id = BookId::new();
+        let row = BookRow {
+            book_id,
+            title: "Title".into(),
+            author: "Author".into(),
+            isbn: "978-4798061702".into(),
+            description: None,
+            owned_by: UserId::new(),
+            owner_name: "Owner".into(),
+            owner_email: "owner@example.com".into(),
+            version: 2,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        assert_eq!(BookCacheKey(book_id).inner(), format!("book:{book_id}"));
+
+        let cached = BookRow::try_from(row.inner())?;
+        assert_eq!(cached.book_id, row.book_id);
+        assert_eq!(cached.owned_by, row.owned_by);
+        assert_eq!(cached.description, None);
+        assert_eq!(cached.version, 2);
+        assert_eq!(cached.created_at, row.created_at);
+
+        assert!(BookRow::try_from("not json".to_string()).is_err());
         Ok(())
     }
     // #[sqlx::test(fixtures("common", "book_checkout"))]
@@ -1,104 +1,125 @@
 use crate::database::{
-    model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
+    ensure_affected, map_db_error,
+    model::checkout::{
+        CheckoutHistoryRow, CheckoutRow, CheckoutStateRow, LostCheckoutRow, OverdueCheckoutRow,
+        PaginatedCheckoutRow,
+    },
     ConnectionPool,
 };
 use async_trait::async_trait;
 
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use derive_new::new;
 use kernel::model::checkout::{
-    event::{CreateCheckout, UpdateReturned},
-    Checkout,
+    event::{CreateCheckout, CreateCheckouts, UpdateReturned},
+    Checkout, CheckoutBatch, CheckoutBatchItem, CheckoutListFilter, CheckoutOutcome, DailyCount,
+    LostCheckout, OverdueCheckout,
+};
+use kernel::model::{
+    book::BookListOptions,
+    id::{BookId, CheckoutId, UserId},
+    list::PaginatedList,
 };
-use kernel::model::id::{BookId, CheckoutId, UserId};
 use kernel::repository::checkout::CheckoutRepository;
 use shared::error::{AppError, AppResult};
+use sqlx::Connection;
 
 #[derive(new)]
 pub struct CheckoutRepositoryImpl {
     db: ConnectionPool,
+    max_per_user: i64,
+    // 貸出日から返却期限までの日数
+    loan_period_days: i64,
 }
 
 #[async_trait]
 impl CheckoutRepository for CheckoutRepositoryImpl {
-    // 貸し出し操作を行う
-    async fn create(&self, event: CreateCheckout) -> AppResult<()> {
+    // 貸し出し操作を行う。借りられる残りの冊数は、他の貸出と競合しないよう同じトランザクション内で数える
+    async fn create(&self, event: CreateCheckout) -> AppResult<i64> {
         let mut tx = self.db.begin().await?;
 
         // トランザクション分離レベルを SERIALIZABLE に設定する
         self.set_transaction_serializable(&mut tx).await?;
 
-        // 事前のチェックとして、以下を調べる。
-        // - 指定の蔵書 ID をもつ蔵書が存在するか
-        // - 存在した場合、この蔵書は貸出中ではないか
-        //
-        // 上記の両方が Yes だった場合、このブロック以降の処理に進む
-        {
-            let res = sqlx::query_as!(
-                CheckoutStateRow,
-                r#"
-                    SELECT
-                    b.book_id,
-                    c.checkout_id AS "checkout_id?: CheckoutId",
-                    NULL AS "user_id?: UserId"
-                    FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1;
-                "#,
-                event.book_id as _
-            )
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
-
-            match res {
-                // 指定した書籍が存在しない場合
-                None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        " 書籍（{}）が見つかりませんでした。",
-                        event.book_id
-                    )))
-                }
-                // 指定した書籍が存在するが貸出中の場合
-                Some(CheckoutStateRow {
-                    checkout_id: Some(_),
-                    ..
-                }) => {
-                    return Err(AppError::UnprocessableEntity(format!(
-                        " 書籍（{}）に対する貸出が既に存在します。",
-                        event.book_id
-                    )))
-                }
-                _ => {} // それ以外は処理続行
-            }
+        // 貸出中の冊数が上限に達している場合は貸し出さない
+        let active = self
+            .count_active_in_tx(&mut tx, event.checked_out_by)
+            .await?;
+        if active >= self.max_per_user {
+            return Err(self.limit_exceeded());
         }
 
-        // 貸し出し処理を行う、すなわち checkouts テーブルにレコードを追加する
-        let checkout_id = CheckoutId::new();
-        let res = sqlx::query!(
-            r#"
-                INSERT INTO checkouts
-                (checkout_id, book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3, $4)
-                ;
-            "#,
-            checkout_id as _,
-            event.book_id as _,
-            event.checked_out_by as _,
+        self.checkout_in_tx(
+            &mut tx,
+            event.book_id,
+            event.checked_out_by,
             event.checked_out_at,
         )
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::SpecificOperationError)?;
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| checkout_conflict_or(e, event.book_id, AppError::TransactionError))?;
+
+        Ok(self.max_per_user - active - 1)
+    }
+
+    // 複数の蔵書をひとつのトランザクションで貸し出す
+    async fn create_batch(&self, event: CreateCheckouts) -> AppResult<CheckoutBatch> {
+        let mut tx = self.db.begin().await?;
+
+        self.set_transaction_serializable(&mut tx).await?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No checkout record has been created".into(),
-            ));
+        // 貸出上限はまとめて借りる冊数全体に対して適用する
+        let active = self
+            .count_active_in_tx(&mut tx, event.checked_out_by)
+            .await?;
+        let mut remaining = (self.max_per_user - active).max(0);
+
+        if !event.best_effort && event.book_ids.len() as i64 > remaining {
+            return Err(self.limit_exceeded());
+        }
+
+        let mut items = Vec::with_capacity(event.book_ids.len());
+        for book_id in event.book_ids {
+            let outcome = if remaining < 1 {
+                CheckoutOutcome::LimitExceeded
+            } else {
+                // 1冊ごとにセーブポイントを置く。直列化の失敗（40001）などで文がエラーになると
+                // トランザクション全体が中断されるため、失敗した蔵書の分だけ巻き戻して続きを処理する
+                let mut savepoint = tx.begin().await.map_err(AppError::TransactionError)?;
+                let res = self
+                    .checkout_in_tx(
+                        &mut savepoint,
+                        book_id,
+                        event.checked_out_by,
+                        event.checked_out_at,
+                    )
+                    .await;
+                match res {
+                    Ok(_) => savepoint.commit().await,
+                    Err(_) => savepoint.rollback().await,
+                }
+                .map_err(AppError::TransactionError)?;
+                match res {
+                    Ok(checkout_id) => {
+                        remaining -= 1;
+                        CheckoutOutcome::CheckedOut(checkout_id)
+                    }
+                    // すべて貸し出すモードでは、途中で失敗したらトランザクションごと破棄する
+                    Err(e) if !event.best_effort => return Err(e),
+                    Err(AppError::EntityNotFound(_)) => CheckoutOutcome::NotFound,
+                    Err(AppError::CheckoutConflict(_)) => CheckoutOutcome::AlreadyCheckedOut,
+                    Err(AppError::BookReserved(_)) => CheckoutOutcome::Reserved,
+                    Err(e) => return Err(e),
+                }
+            };
+            items.push(CheckoutBatchItem { book_id, outcome });
         }
 
         tx.commit().await.map_err(AppError::TransactionError)?;
 
-        Ok(())
+        Ok(CheckoutBatch { items, remaining })
     }
 
     // 返却操作を行う
@@ -134,16 +155,11 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             )
             .fetch_optional(&mut *tx)
             .await
-            .map_err(AppError::SpecificOperationError)?;
+            .map_err(map_db_error)?;
 
             match res {
                 // 指定した書籍がそもそも存在しない場合
-                None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        " 書籍（{}）が見つかりませんでした。",
-                        event.book_id
-                    )))
-                }
+                None => return Err(AppError::not_found("Book", event.book_id)),
                 // 指定した書籍が貸出中であり、貸出 ID または借りたユーザーが異なる場合
                 Some(CheckoutStateRow {
                     checkout_id: Some(c),
@@ -176,13 +192,9 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         )
         .execute(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No returning record has been updated".into(),
-            ));
-        }
+        ensure_affected(&res, AppError::not_found("Checkout", event.checkout_id))?;
 
         // 上記処理が成功したら checkouts テーブルから該当貸出 ID のレコードを削除する
         let res = sqlx::query!(
@@ -193,19 +205,35 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         )
         .execute(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No checkout record has been deleted".into(),
-            ));
-        }
+        ensure_affected(&res, AppError::not_found("Checkout", event.checkout_id))?;
 
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
 
+    // 誤って登録した貸出を取り消す。返却とは異なり、貸出履歴には残さない。
+    // 取り消せるのは未返却の貸出だけで、返却済み（checkouts に残っていない）の場合は見つからない扱いとする
+    async fn cancel(&self, checkout_id: CheckoutId, book_id: BookId) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM checkouts
+                WHERE checkout_id = $1
+                AND book_id = $2
+                ;
+            "#,
+            checkout_id as _,
+            book_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        ensure_affected(&res, AppError::not_found("Checkout", checkout_id))
+    }
+
     // すべての未返却の貸出情報を取得する
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>> {
         // checkouts テーブルにあるレコードを全件抽出する
@@ -231,7 +259,7 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         .fetch_all(self.db.inner_ref())
         .await
         .map(|rows| rows.into_iter().map(Checkout::from).collect())
-        .map_err(AppError::SpecificOperationError)
+        .map_err(map_db_error)
     }
 
     // ユーザー ID に紐づく未返却の貸出情報を取得する
@@ -260,54 +288,481 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         .fetch_all(self.db.inner_ref())
         .await
         .map(|rows| rows.into_iter().map(Checkout::from).collect())
-        .map_err(AppError::SpecificOperationError)
+        .map_err(map_db_error)
+    }
+
+    // ユーザーが借りている蔵書を、貸出日時の古い順にページ単位で取得する
+    async fn find_active_by_user(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>> {
+        let BookListOptions { limit, offset, .. } = options;
+
+        let rows: Vec<PaginatedCheckoutRow> = sqlx::query_as!(
+            PaginatedCheckoutRow,
+            r#"
+                SELECT
+                COUNT(*) OVER() AS "total!",
+                c.checkout_id,
+                c.book_id,
+                c.user_id,
+                c.checked_out_at,
+                b.title,
+                b.author,
+                b.isbn
+                FROM checkouts AS c
+                INNER JOIN books AS b USING(book_id)
+                WHERE c.user_id = $1
+                ORDER BY c.checked_out_at ASC
+                LIMIT $2
+                OFFSET $3
+            "#,
+            user_id as _,
+            limit,
+            offset,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let items = rows.into_iter().map(Checkout::from).collect();
+
+        Ok(PaginatedList {
+            total: Some(total),
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    // 全蔵書の貸出（返却済みも含む）を、貸出日時の新しい順にページ単位で取得する
+    async fn find_all(
+        &self,
+        filter: CheckoutListFilter,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>> {
+        let CheckoutListFilter {
+            active_only,
+            user_id,
+            book_id,
+        } = filter;
+        let BookListOptions { limit, offset, .. } = options;
+
+        let rows: Vec<CheckoutHistoryRow> = sqlx::query_as!(
+            CheckoutHistoryRow,
+            r#"
+                SELECT
+                COUNT(*) OVER() AS "total!",
+                h.checkout_id AS "checkout_id!: CheckoutId",
+                h.book_id AS "book_id!: BookId",
+                h.user_id AS "user_id!: UserId",
+                h.checked_out_at AS "checked_out_at!",
+                h.returned_at,
+                b.title,
+                b.author,
+                b.isbn
+                FROM (
+                    SELECT checkout_id, book_id, user_id, checked_out_at,
+                        NULL::TIMESTAMPTZ AS returned_at
+                    FROM checkouts
+                    UNION ALL
+                    SELECT checkout_id, book_id, user_id, checked_out_at, returned_at
+                    FROM returned_checkouts
+                    WHERE NOT $1
+                ) AS h
+                INNER JOIN books AS b USING(book_id)
+                WHERE ($2::UUID IS NULL OR h.user_id = $2)
+                AND ($3::UUID IS NULL OR h.book_id = $3)
+                ORDER BY h.checked_out_at DESC
+                LIMIT $4
+                OFFSET $5
+            "#,
+            active_only,
+            user_id as _,
+            book_id as _,
+            limit,
+            offset,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let items = rows.into_iter().map(Checkout::from).collect();
+
+        Ok(PaginatedList {
+            total: Some(total),
+            limit,
+            offset,
+            items,
+        })
     }
 
     // 蔵書の貸し出し履歴（返却済みも含む）を取得する
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>> {
-        // このメソッドでは、貸出中・返却済みの両方を取得して
-        // 蔵書に対する貸出履歴の一覧として返す必要がある。
-        // そのため、未返却の貸出情報と返却済みの貸出情報をそれぞれ取得し、
-        // 未返却の貸出情報があれば Vec に挿入して返す、という実装とする。
-        // 未返却の貸出情報を取得
-        let checkout: Option<Checkout> = self.find_unreturned_by_book_id(book_id).await?;
-        // 返却済みの貸出情報を取得
-        let mut checkout_histories: Vec<Checkout> = sqlx::query_as!(
-            ReturnedCheckoutRow,
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>> {
+        let BookListOptions { limit, offset, .. } = options;
+
+        // 貸出中・返却済みの両方をまとめて、貸出日時の新しい順に並べる
+        let rows: Vec<CheckoutHistoryRow> = sqlx::query_as!(
+            CheckoutHistoryRow,
             r#"
                 SELECT
-                rc.checkout_id,
-                rc.book_id,
-                rc.user_id,
-                rc.checked_out_at,
-                rc.returned_at,
+                COUNT(*) OVER() AS "total!",
+                h.checkout_id AS "checkout_id!: CheckoutId",
+                h.book_id AS "book_id!: BookId",
+                h.user_id AS "user_id!: UserId",
+                h.checked_out_at AS "checked_out_at!",
+                h.returned_at,
                 b.title,
                 b.author,
                 b.isbn
-                FROM returned_checkouts AS rc
+                FROM (
+                    SELECT checkout_id, book_id, user_id, checked_out_at,
+                        NULL::TIMESTAMPTZ AS returned_at
+                    FROM checkouts
+                    UNION ALL
+                    SELECT checkout_id, book_id, user_id, checked_out_at, returned_at
+                    FROM returned_checkouts
+                ) AS h
                 INNER JOIN books AS b USING(book_id)
-                WHERE rc.book_id = $1
-                ORDER BY rc.checked_out_at DESC
+                WHERE h.book_id = $1
+                ORDER BY h.checked_out_at DESC
+                LIMIT $2
+                OFFSET $3
             "#,
-            book_id as _
+            book_id as _,
+            limit,
+            offset,
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
-        .into_iter()
-        .map(Checkout::from)
-        .collect();
-
-        // 貸出中である場合は返却済みの履歴の先頭に追加する
-        if let Some(co) = checkout {
-            checkout_histories.insert(0, co);
-        }
+        .map_err(map_db_error)?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let items = rows.into_iter().map(Checkout::from).collect();
 
-        Ok(checkout_histories)
+        Ok(PaginatedList {
+            total: Some(total),
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    // ユーザーが借りた蔵書を、返却済みも含めて貸出日時の新しい順にページ単位で取得する
+    async fn find_history_by_user(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>> {
+        let BookListOptions { limit, offset, .. } = options;
+
+        // find_history_by_book_id の SQL の絞り込みを、蔵書 ID からユーザー ID に替えたものである
+        let rows: Vec<CheckoutHistoryRow> = sqlx::query_as!(
+            CheckoutHistoryRow,
+            r#"
+                SELECT
+                COUNT(*) OVER() AS "total!",
+                h.checkout_id AS "checkout_id!: CheckoutId",
+                h.book_id AS "book_id!: BookId",
+                h.user_id AS "user_id!: UserId",
+                h.checked_out_at AS "checked_out_at!",
+                h.returned_at,
+                b.title,
+                b.author,
+                b.isbn
+                FROM (
+                    SELECT checkout_id, book_id, user_id, checked_out_at,
+                        NULL::TIMESTAMPTZ AS returned_at
+                    FROM checkouts
+                    UNION ALL
+                    SELECT checkout_id, book_id, user_id, checked_out_at, returned_at
+                    FROM returned_checkouts
+                ) AS h
+                INNER JOIN books AS b USING(book_id)
+                WHERE h.user_id = $1
+                ORDER BY h.checked_out_at DESC
+                LIMIT $2
+                OFFSET $3
+            "#,
+            user_id as _,
+            limit,
+            offset,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let items = rows.into_iter().map(Checkout::from).collect();
+
+        Ok(PaginatedList {
+            total: Some(total),
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    // ユーザーがあと何冊借りられるか（上限 - 貸出中の冊数）を取得する
+    async fn count_remaining_by_user_id(&self, user_id: UserId) -> AppResult<i64> {
+        let active = sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "count!"
+                FROM checkouts
+                WHERE user_id = $1
+                ;
+            "#,
+            user_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok((self.max_per_user - active).max(0))
+    }
+
+    // 貸出日から指定日数以上が経過した未返却の貸出を、延滞日数の長い順に取得する
+    async fn find_long_overdue(&self, days: i64) -> AppResult<Vec<LostCheckout>> {
+        let now = Utc::now();
+        let threshold = now - Duration::days(days);
+
+        sqlx::query_as!(
+            LostCheckoutRow,
+            r#"
+                SELECT
+                c.checkout_id,
+                c.book_id,
+                b.title,
+                c.user_id,
+                u.name AS user_name,
+                u.email,
+                c.checked_out_at
+                FROM checkouts AS c
+                INNER JOIN books AS b USING(book_id)
+                INNER JOIN users AS u ON u.user_id = c.user_id
+                WHERE c.checked_out_at <= $1
+                ORDER BY c.checked_out_at ASC
+                ;
+            "#,
+            threshold
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|row| row.into_lost_checkout(now))
+                .collect()
+        })
+        .map_err(map_db_error)
+    }
+
+    // 指定日時の時点で返却期限を過ぎている未返却の貸出を、期限の古い順に取得する
+    async fn find_overdue(&self, as_of: DateTime<Utc>) -> AppResult<Vec<OverdueCheckout>> {
+        sqlx::query_as!(
+            OverdueCheckoutRow,
+            r#"
+                SELECT
+                c.checkout_id,
+                c.book_id,
+                b.title,
+                c.user_id,
+                u.name AS user_name,
+                u.email,
+                c.checked_out_at,
+                c.due_at
+                FROM checkouts AS c
+                INNER JOIN books AS b USING(book_id)
+                INNER JOIN users AS u ON u.user_id = c.user_id
+                WHERE c.due_at < $1
+                ORDER BY c.due_at ASC
+                ;
+            "#,
+            as_of
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(OverdueCheckout::from).collect())
+        .map_err(map_db_error)
+    }
+
+    // 指定期間の1日ごとの貸出件数を、貸出のなかった日も 0 件として含めて取得する
+    async fn daily_counts(&self, from: NaiveDate, to: NaiveDate) -> AppResult<Vec<DailyCount>> {
+        // 返却済みの貸出も、貸し出した日の件数に数える
+        sqlx::query_as!(
+            DailyCount,
+            r#"
+                SELECT
+                d.day::DATE AS "date!",
+                COUNT(c.checked_out_at) AS "count!"
+                FROM generate_series($1::DATE, $2::DATE, INTERVAL '1 day') AS d(day)
+                LEFT OUTER JOIN (
+                    SELECT checked_out_at FROM checkouts
+                    UNION ALL
+                    SELECT checked_out_at FROM returned_checkouts
+                ) AS c
+                ON (c.checked_out_at AT TIME ZONE 'UTC')::DATE = d.day::DATE
+                GROUP BY d.day
+                ORDER BY d.day
+                ;
+            "#,
+            from,
+            to,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)
     }
 }
 
 impl CheckoutRepositoryImpl {
+    // create, create_batch メソッドで共通の、トランザクション内でユーザーの貸出中の冊数を数える処理
+    async fn count_active_in_tx(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: UserId,
+    ) -> AppResult<i64> {
+        sqlx::query_scalar!(
+            r#"
+                SELECT COUNT(*) AS "count!"
+                FROM checkouts
+                WHERE user_id = $1
+                ;
+            "#,
+            user_id as _
+        )
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(map_db_error)
+    }
+
+    fn limit_exceeded(&self) -> AppError {
+        AppError::CheckoutLimitExceeded(format!(
+            " 貸出上限（{}冊）を超えるため貸し出せません。",
+            self.max_per_user
+        ))
+    }
+
+    // create, create_batch メソッドで共通の、トランザクション内で1冊を貸し出す処理
+    async fn checkout_in_tx(
+        &self,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        book_id: BookId,
+        checked_out_by: UserId,
+        checked_out_at: DateTime<Utc>,
+    ) -> AppResult<CheckoutId> {
+        // 事前のチェックとして、以下を調べる。
+        // - 指定の蔵書 ID をもつ蔵書が存在するか
+        // - 存在した場合、この蔵書は貸出中ではないか
+        //
+        // 上記の両方が Yes だった場合、このブロック以降の処理に進む
+        {
+            let res = sqlx::query_as!(
+                CheckoutStateRow,
+                r#"
+                    SELECT
+                    b.book_id,
+                    c.checkout_id AS "checkout_id?: CheckoutId",
+                    NULL AS "user_id?: UserId"
+                    FROM books AS b
+                    LEFT OUTER JOIN checkouts AS c USING(book_id)
+                    WHERE book_id = $1
+                    AND b.deleted_at IS NULL;
+                "#,
+                book_id as _
+            )
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(map_db_error)?;
+
+            match res {
+                // 指定した書籍が存在しない場合
+                None => return Err(AppError::not_found("Book", book_id)),
+                // 指定した書籍が存在するが貸出中の場合
+                Some(CheckoutStateRow {
+                    checkout_id: Some(_),
+                    ..
+                }) => return Err(checkout_conflict(book_id)),
+                _ => {} // それ以外は処理続行
+            }
+        }
+
+        // 予約の順番待ちがある蔵書は、先頭に並んでいるユーザーだけが借りられる。
+        // 借りたユーザーの予約は使い終えたものとして削除し、後ろの予約を繰り上げる
+        let head = sqlx::query_scalar!(
+            r#"
+                SELECT user_id AS "user_id: UserId"
+                FROM reservations
+                WHERE book_id = $1
+                ORDER BY queued_order
+                LIMIT 1
+                ;
+            "#,
+            book_id as _,
+        )
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(map_db_error)?;
+        match head {
+            Some(user_id) if user_id != checked_out_by => {
+                return Err(AppError::BookReserved(format!(
+                    " 書籍（{}）は他のユーザーが予約しています。",
+                    book_id
+                )))
+            }
+            Some(_) => {
+                sqlx::query!(
+                    r#"
+                        DELETE FROM reservations
+                        WHERE book_id = $1
+                        AND user_id = $2
+                        ;
+                    "#,
+                    book_id as _,
+                    checked_out_by as _,
+                )
+                .execute(&mut **tx)
+                .await
+                .map_err(map_db_error)?;
+            }
+            None => {}
+        }
+
+        // 貸し出し処理を行う、すなわち checkouts テーブルにレコードを追加する。
+        // 上のチェックの後に別のリクエストが同じ蔵書を貸し出していた場合に備えて、
+        // 貸出中のレコードがないときだけ追加し、追加できなければ貸出の競合とする
+        let checkout_id = CheckoutId::new();
+        let due_at = checked_out_at + Duration::days(self.loan_period_days);
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO checkouts
+                (checkout_id, book_id, user_id, checked_out_at, due_at)
+                SELECT $1, $2, $3, $4, $5
+                WHERE NOT EXISTS (SELECT 1 FROM checkouts WHERE book_id = $2)
+                ON CONFLICT (book_id) DO NOTHING
+                ;
+            "#,
+            checkout_id as _,
+            book_id as _,
+            checked_out_by as _,
+            checked_out_at,
+            due_at,
+        )
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| checkout_conflict_or(e, book_id, map_db_error))?;
+
+        ensure_affected(&res, checkout_conflict(book_id))?;
+
+        Ok(checkout_id)
+    }
+
     // create, update_returned メソッドでのトランザクションを利用するにあたり
     // トランザクション分離レベルを SERIALIZABLE にするために
     // 内部的に使うメソッド
@@ -318,12 +773,12 @@ impl CheckoutRepositoryImpl {
         sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
             .execute(&mut **tx)
             .await
-            .map_err(AppError::SpecificOperationError)?;
+            .map_err(map_db_error)?;
         Ok(())
     }
 
-    // find_history_by_book_id で未返却の貸出情報を取得するために
-    // 内部的に使うメソッド
+    // テストで蔵書の未返却の貸出情報を取得するために使うメソッド
+    #[cfg(test)]
     async fn find_unreturned_by_book_id(&self, book_id: BookId) -> AppResult<Option<Checkout>> {
         let res = sqlx::query_as!(
             CheckoutRow,
@@ -344,23 +799,50 @@ impl CheckoutRepositoryImpl {
         )
         .fetch_optional(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
+        .map_err(map_db_error)?
         .map(Checkout::from);
 
         Ok(res)
     }
 }
 
+fn checkout_conflict(book_id: BookId) -> AppError {
+    AppError::CheckoutConflict(format!(
+        " 書籍（{}）に対する貸出が既に存在します。",
+        book_id
+    ))
+}
+
+// 同じ蔵書を同時に貸し出そうとした場合、後から確定する側のトランザクションは
+// 一意制約違反（23505）または直列化の失敗（40001）になるため、貸出の競合として扱う
+fn checkout_conflict_or(
+    e: sqlx::Error,
+    book_id: BookId,
+    otherwise: fn(sqlx::Error) -> AppError,
+) -> AppError {
+    let conflicted = e
+        .as_database_error()
+        .and_then(|db| db.code())
+        .is_some_and(|code| code == "23505" || code == "40001");
+    if conflicted {
+        checkout_conflict(book_id)
+    } else {
+        otherwise(e)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
-    use kernel::model::checkout::CheckoutBook;
+    use chrono::{SubsecRound, Utc};
+    use kernel::model::{checkout::CheckoutBook, reservation::event::CreateReservation};
 
     use super::*;
+    use crate::repository::reservation::ReservationRepositoryImpl;
+    use kernel::repository::reservation::ReservationRepository;
     use std::str::FromStr;
 
     fn init_repo(pool: sqlx::PgPool) -> (CheckoutRepositoryImpl, UserId, UserId, BookId) {
-        let repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool));
+        let repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 3, 14);
 
         // 事前登録したユーザー＆蔵書のID（fixtures/checkout.sql参照）
         let user_id1 = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b").unwrap();
@@ -370,6 +852,14 @@ mod tests {
         (repo, user_id1, user_id2, book_id1)
     }
 
+    fn options() -> BookListOptions {
+        BookListOptions {
+            limit: 20,
+            offset: 0,
+            ..Default::default()
+        }
+    }
+
     #[sqlx::test(fixtures("common", "checkout"))]
     async fn test_checkout_and_return(pool: sqlx::PgPool) -> anyhow::Result<()> {
         let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
@@ -416,7 +906,7 @@ mod tests {
                     checked_out_at: Utc::now(),
                 })
                 .await;
-            assert!(res.is_err());
+            assert!(matches!(res, Err(AppError::CheckoutConflict(_))));
 
             let co = co.unwrap();
 
@@ -466,6 +956,75 @@ mod tests {
         Ok(())
     }
 
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_cancel(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, _, book_id1) = init_repo(pool);
+
+        repo.create(CreateCheckout {
+            book_id: book_id1,
+            checked_out_by: user_id1,
+            checked_out_at: Utc::now(),
+        })
+        .await?;
+        let co = repo.find_unreturned_by_book_id(book_id1).await?.unwrap();
+
+        // 貸出 ID と蔵書 ID の組み合わせが一致しなければ取り消せない
+        let res = repo.cancel(co.id, BookId::new()).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 取り消すと貸出中でなくなり、履歴にも残らない
+        repo.cancel(co.id, book_id1).await?;
+        assert!(repo.find_unreturned_by_book_id(book_id1).await?.is_none());
+        let history = repo.find_history_by_book_id(book_id1, options()).await?;
+        assert_eq!(history.total, Some(0));
+
+        // 取り消し済みの貸出はもう取り消せない
+        let res = repo.cancel(co.id, book_id1).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 返却済みの貸出も取り消せない
+        repo.create(CreateCheckout {
+            book_id: book_id1,
+            checked_out_by: user_id1,
+            checked_out_at: Utc::now(),
+        })
+        .await?;
+        let co = repo.find_unreturned_by_book_id(book_id1).await?.unwrap();
+        repo.update_returned(UpdateReturned {
+            checkout_id: co.id,
+            book_id: book_id1,
+            returned_by: user_id1,
+            returned_at: Utc::now(),
+        })
+        .await?;
+        let res = repo.cancel(co.id, book_id1).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_concurrent_checkout(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+
+        // 同じ蔵書を2人のユーザーが同時に借りようとしても、貸し出せるのは1件だけ
+        let checkout = |user_id| CreateCheckout::new(book_id1, user_id, Utc::now());
+        let (res1, res2) = tokio::join!(
+            repo.create(checkout(user_id1)),
+            repo.create(checkout(user_id2))
+        );
+        let results = [res1, res2];
+        assert_eq!(results.iter().filter(|res| res.is_ok()).count(), 1);
+        assert!(results
+            .iter()
+            .any(|res| matches!(res, Err(AppError::CheckoutConflict(_)))));
+
+        let unreturned = repo.find_unreturned_all().await?;
+        assert_eq!(unreturned.len(), 1);
+
+        Ok(())
+    }
+
     #[sqlx::test(fixtures("common", "checkout"))]
     async fn test_checkout_list(pool: sqlx::PgPool) -> anyhow::Result<()> {
         let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
@@ -492,8 +1051,8 @@ mod tests {
                 let res = repo.find_unreturned_by_user_id(user_id2).await?;
                 assert_eq!(res.len(), 0);
 
-                let res = repo.find_history_by_book_id(book_id1).await?;
-                assert_eq!(res.len(), 1);
+                let res = repo.find_history_by_book_id(book_id1, options()).await?;
+                assert_eq!(res.items.len(), 1);
             }
 
             repo.update_returned(UpdateReturned {
@@ -515,8 +1074,8 @@ mod tests {
                 let res = repo.find_unreturned_by_user_id(user_id2).await?;
                 assert_eq!(res.len(), 0);
 
-                let res = repo.find_history_by_book_id(book_id1).await?;
-                assert_eq!(res.len(), 1);
+                let res = repo.find_history_by_book_id(book_id1, options()).await?;
+                assert_eq!(res.items.len(), 1);
             }
         }
 
@@ -542,8 +1101,8 @@ mod tests {
                 let res = repo.find_unreturned_by_user_id(user_id2).await?;
                 assert_eq!(res.len(), 1);
 
-                let res = repo.find_history_by_book_id(book_id1).await?;
-                assert_eq!(res.len(), 2);
+                let res = repo.find_history_by_book_id(book_id1, options()).await?;
+                assert_eq!(res.items.len(), 2);
             }
 
             repo.update_returned(UpdateReturned {
@@ -565,11 +1124,561 @@ mod tests {
                 let res = repo.find_unreturned_by_user_id(user_id2).await?;
                 assert_eq!(res.len(), 0);
 
-                let res = repo.find_history_by_book_id(book_id1).await?;
-                assert_eq!(res.len(), 2);
+                let res = repo.find_history_by_book_id(book_id1, options()).await?;
+                assert_eq!(res.items.len(), 2);
             }
         }
 
         Ok(())
     }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_checkout_limit(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 1, 14);
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let book_id1 = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+
+        // 上限まで借りた後は、次の貸し出しが失敗する
+        repo.create(CreateCheckout::new(book_id1, user_id, Utc::now()))
+            .await?;
+        let res = repo
+            .create(CreateCheckout::new(book_id2, user_id, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::CheckoutLimitExceeded(_))));
+        assert_eq!(repo.find_unreturned_by_user_id(user_id).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_active_by_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+
+        // user_id1 が2冊、user_id2 は何も借りていない状態にする
+        // DB にはミリ秒単位で保存されるため、比較できるようあらかじめ切り捨てておく
+        let checked_out_at = (Utc::now() - Duration::days(3)).trunc_subsecs(3);
+        repo.create(CreateCheckout::new(book_id1, user_id1, checked_out_at))
+            .await?;
+        repo.create(CreateCheckout::new(book_id2, user_id1, Utc::now()))
+            .await?;
+
+        // 貸出日時の古い順に並び、書籍の情報と貸出日時を含む
+        let page = |offset| BookListOptions {
+            limit: 1,
+            offset,
+            ..Default::default()
+        };
+        let res = repo.find_active_by_user(user_id1, page(0)).await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].book.book_id, book_id1);
+        assert!(!res.items[0].book.title.is_empty());
+        assert_eq!(res.items[0].checked_out_at, checked_out_at);
+
+        let res = repo.find_active_by_user(user_id1, page(1)).await?;
+        assert_eq!(res.items[0].book.book_id, book_id2);
+
+        let res = repo.find_active_by_user(user_id2, options()).await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_checkout_history(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let checked_out_at = Utc::now() - Duration::days(2);
+
+        // user_id1 が借りて返却した後に、user_id2 が借りている状態にする
+        repo.create(CreateCheckout::new(book_id1, user_id1, checked_out_at))
+            .await?;
+        let co = repo.find_unreturned_by_book_id(book_id1).await?.unwrap();
+        repo.update_returned(UpdateReturned::new(
+            co.id,
+            book_id1,
+            user_id1,
+            checked_out_at + Duration::days(1),
+        ))
+        .await?;
+        repo.create(CreateCheckout::new(book_id1, user_id2, Utc::now()))
+            .await?;
+
+        // 貸出中の履歴が先頭に、返却済みの履歴がその後に並ぶ
+        let page = |offset| BookListOptions {
+            limit: 1,
+            offset,
+            ..Default::default()
+        };
+        let res = repo.find_history_by_book_id(book_id1, page(0)).await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].checked_out_by, user_id2);
+        assert!(res.items[0].returned_at.is_none());
+
+        let res = repo.find_history_by_book_id(book_id1, page(1)).await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items[0].checked_out_by, user_id1);
+        assert!(res.items[0].returned_at.is_some());
+
+        // 貸出履歴のない蔵書は空になる
+        let res = repo
+            .find_history_by_book_id(BookId::new(), options())
+            .await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_history_by_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+        let checked_out_at = Utc::now() - Duration::days(2);
+
+        // user_id1 が book_id1 を借りて返却した後に book_id2 を借り、
+        // user_id2 が book_id1 を借りている状態にする
+        repo.create(CreateCheckout::new(book_id1, user_id1, checked_out_at))
+            .await?;
+        let co = repo.find_unreturned_by_book_id(book_id1).await?.unwrap();
+        let returned_at = checked_out_at + Duration::days(1);
+        repo.update_returned(UpdateReturned::new(co.id, book_id1, user_id1, returned_at))
+            .await?;
+        repo.create(CreateCheckout::new(book_id2, user_id1, Utc::now()))
+            .await?;
+        repo.create(CreateCheckout::new(book_id1, user_id2, Utc::now()))
+            .await?;
+
+        // 貸出中の book_id2 が先頭に、返却済みの book_id1 がその後に並ぶ
+        let res = repo.find_history_by_user(user_id1, options()).await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items[0].book.book_id, book_id2);
+        assert!(res.items[0].returned_at.is_none());
+        assert_eq!(res.items[1].book.book_id, book_id1);
+        assert!(res.items[1].returned_at.is_some());
+        assert!(!res.items[1].book.title.is_empty());
+        assert!(res.items.iter().all(|c| c.checked_out_by == user_id1));
+
+        // ページ単位でも total は履歴全体の件数になる
+        let res = repo
+            .find_history_by_user(
+                user_id1,
+                BookListOptions {
+                    limit: 1,
+                    offset: 1,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items.len(), 1);
+        assert_eq!(res.items[0].book.book_id, book_id1);
+
+        // 貸出履歴のないユーザーは空になる
+        let res = repo.find_history_by_user(UserId::new(), options()).await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_all(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+        let checked_out_at = Utc::now() - Duration::days(2);
+
+        // book_id1 は user_id1 が借りて返却済み、book_id2 は user_id2 が借りている状態にする
+        repo.create(CreateCheckout::new(book_id1, user_id1, checked_out_at))
+            .await?;
+        let co = repo.find_unreturned_by_book_id(book_id1).await?.unwrap();
+        repo.update_returned(UpdateReturned::new(
+            co.id,
+            book_id1,
+            user_id1,
+            checked_out_at + Duration::days(1),
+        ))
+        .await?;
+        repo.create(CreateCheckout::new(book_id2, user_id2, Utc::now()))
+            .await?;
+
+        // 絞り込まない場合は返却済みも含めて新しい順に並ぶ
+        let res = repo
+            .find_all(CheckoutListFilter::default(), options())
+            .await?;
+        assert_eq!(res.total, Some(2));
+        assert_eq!(res.items[0].book.book_id, book_id2);
+        assert_eq!(res.items[1].book.book_id, book_id1);
+
+        let res = repo
+            .find_all(
+                CheckoutListFilter {
+                    active_only: true,
+                    ..Default::default()
+                },
+                options(),
+            )
+            .await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(res.items[0].checked_out_by, user_id2);
+
+        let res = repo
+            .find_all(
+                CheckoutListFilter {
+                    user_id: Some(user_id1),
+                    ..Default::default()
+                },
+                options(),
+            )
+            .await?;
+        assert_eq!(res.total, Some(1));
+        assert!(res.items[0].returned_at.is_some());
+
+        // 条件はすべて満たす貸出だけに絞り込む
+        let res = repo
+            .find_all(
+                CheckoutListFilter {
+                    active_only: true,
+                    book_id: Some(book_id1),
+                    ..Default::default()
+                },
+                options(),
+            )
+            .await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_count_remaining(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        // 2冊目の蔵書（fixtures/checkout.sql参照）
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11").unwrap();
+
+        assert_eq!(repo.count_remaining_by_user_id(user_id1).await?, 3);
+
+        // 貸し出すごとに残り冊数が減り、貸出の結果としても同じ冊数を返す
+        let remaining = repo
+            .create(CreateCheckout::new(book_id1, user_id1, Utc::now()))
+            .await?;
+        assert_eq!(remaining, 2);
+        assert_eq!(repo.count_remaining_by_user_id(user_id1).await?, 2);
+
+        let remaining = repo
+            .create(CreateCheckout::new(book_id2, user_id1, Utc::now()))
+            .await?;
+        assert_eq!(remaining, 1);
+        assert_eq!(repo.count_remaining_by_user_id(user_id1).await?, 1);
+
+        // 他のユーザーの残り冊数には影響しない
+        assert_eq!(repo.count_remaining_by_user_id(user_id2).await?, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_overdue(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11").unwrap();
+
+        // 貸出期間は14日。20日前と10日前に貸し出された蔵書を用意する
+        let checked_out_at = (Utc::now() - Duration::days(20)).trunc_subsecs(3);
+        repo.create(CreateCheckout::new(book_id1, user_id1, checked_out_at))
+            .await?;
+        repo.create(CreateCheckout::new(
+            book_id2,
+            user_id2,
+            Utc::now() - Duration::days(10),
+        ))
+        .await?;
+
+        // 返却期限を過ぎたものだけが延滞になり、期限は貸出日から14日後になる
+        let res = repo.find_overdue(Utc::now()).await?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].book_id, book_id1);
+        assert_eq!(res[0].checked_out_by, user_id1);
+        assert_eq!(res[0].due_at, checked_out_at + Duration::days(14));
+
+        // 基準日時を後ろにずらすと、両方が期限の古い順に並ぶ
+        let res = repo.find_overdue(Utc::now() + Duration::days(7)).await?;
+        let book_ids = res.iter().map(|co| co.book_id).collect::<Vec<_>>();
+        assert_eq!(book_ids, vec![book_id1, book_id2]);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_long_overdue(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11").unwrap();
+
+        // 40日前と10日前に貸し出された蔵書を用意する
+        repo.create(CreateCheckout::new(
+            book_id1,
+            user_id1,
+            Utc::now() - Duration::days(40),
+        ))
+        .await?;
+        repo.create(CreateCheckout::new(
+            book_id2,
+            user_id2,
+            Utc::now() - Duration::days(10),
+        ))
+        .await?;
+
+        // 30日を超えるものだけが紛失扱いになる
+        let res = repo.find_long_overdue(30).await?;
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].book_id, book_id1);
+        assert_eq!(res[0].email, "adam.smith@example.com");
+        assert_eq!(res[0].days_overdue, 40);
+
+        // 延滞日数の長い順に並ぶ
+        let res = repo.find_long_overdue(5).await?;
+        let book_ids = res.iter().map(|co| co.book_id).collect::<Vec<_>>();
+        assert_eq!(book_ids, vec![book_id1, book_id2]);
+        assert_eq!(res[1].days_overdue, 10);
+
+        // 貸出が新しすぎる場合は何も返さない
+        let res = repo.find_long_overdue(60).await?;
+        assert!(res.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_create_batch(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 上限1冊のユーザーに2冊まとめて貸し出す
+        let repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 1, 14);
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let book_id1 = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+        let batch = |book_ids: Vec<BookId>, best_effort: bool| {
+            CreateCheckouts::new(book_ids, user_id, Utc::now(), best_effort)
+        };
+
+        // すべて貸し出すモードでは、上限を超える場合は1冊も貸し出さない
+        let res = repo
+            .create_batch(batch(vec![book_id1, book_id2], false))
+            .await;
+        assert!(matches!(res, Err(AppError::CheckoutLimitExceeded(_))));
+        assert!(repo.find_unreturned_by_user_id(user_id).await?.is_empty());
+
+        // 存在しない蔵書が含まれる場合も1冊も貸し出さない
+        let repo = CheckoutRepositoryImpl::new(repo.db, 3, 14);
+        let res = repo
+            .create_batch(batch(vec![book_id1, BookId::new()], false))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(repo.find_unreturned_by_user_id(user_id).await?.is_empty());
+
+        // 可能な分だけ貸し出すモードでは、蔵書ごとの結果を返す
+        let repo = CheckoutRepositoryImpl::new(repo.db, 2, 14);
+        let unknown = BookId::new();
+        let res = repo
+            .create_batch(batch(vec![book_id1, unknown, book_id1, book_id2], true))
+            .await?;
+        let outcomes: Vec<_> = res
+            .items
+            .iter()
+            .map(|item| (item.book_id, &item.outcome))
+            .collect();
+        assert!(matches!(outcomes[0], (id, CheckoutOutcome::CheckedOut(_)) if id == book_id1));
+        assert_eq!(outcomes[1], (unknown, &CheckoutOutcome::NotFound));
+        assert_eq!(outcomes[2], (book_id1, &CheckoutOutcome::AlreadyCheckedOut));
+        assert!(matches!(outcomes[3], (id, CheckoutOutcome::CheckedOut(_)) if id == book_id2));
+        assert_eq!(res.remaining, 0);
+        assert_eq!(repo.find_unreturned_by_user_id(user_id).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_create_batch_after_serialization_failure(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let (repo, user_id, _, book_id1) = init_repo(pool.clone());
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+
+        // book_id1 の貸出を追加しようとすると直列化の失敗になるようにする
+        sqlx::query(&format!(
+            r#"
+                CREATE FUNCTION fail_checkout() RETURNS trigger AS $$
+                BEGIN
+                    IF NEW.book_id = '{book_id1}' THEN
+                        RAISE EXCEPTION 'could not serialize access'
+                        USING ERRCODE = 'serialization_failure';
+                    END IF;
+                    RETURN NEW;
+                END;
+                $$ LANGUAGE plpgsql;
+            "#
+        ))
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE TRIGGER fail_checkout BEFORE INSERT ON checkouts \
+             FOR EACH ROW EXECUTE FUNCTION fail_checkout()",
+        )
+        .execute(&pool)
+        .await?;
+
+        // 失敗した蔵書の後に続く蔵書も、中断されたトランザクションに巻き込まれずに貸し出せる
+        let res = repo
+            .create_batch(CreateCheckouts::new(
+                vec![book_id1, book_id2],
+                user_id,
+                Utc::now(),
+                true,
+            ))
+            .await?;
+        assert_eq!(res.items[0].outcome, CheckoutOutcome::AlreadyCheckedOut);
+        assert!(matches!(
+            res.items[1].outcome,
+            CheckoutOutcome::CheckedOut(_)
+        ));
+
+        let checkouts = repo.find_unreturned_by_user_id(user_id).await?;
+        assert_eq!(checkouts.len(), 1);
+        assert_eq!(checkouts[0].book.book_id, book_id2);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_checkout_reserved_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let reservation_repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let (repo, user_id1, user_id2, book_id1) = init_repo(pool);
+
+        // user_id1, user_id2 の順に予約している
+        reservation_repo
+            .create(CreateReservation::new(book_id1, user_id1))
+            .await?;
+        reservation_repo
+            .create(CreateReservation::new(book_id1, user_id2))
+            .await?;
+
+        // 順番待ちの先頭以外のユーザーは借りられない
+        let res = repo
+            .create(CreateCheckout::new(book_id1, user_id2, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::BookReserved(_))));
+        let res = repo
+            .create_batch(CreateCheckouts::new(
+                vec![book_id1],
+                user_id2,
+                Utc::now(),
+                true,
+            ))
+            .await?;
+        assert_eq!(res.items[0].outcome, CheckoutOutcome::Reserved);
+
+        // 先頭のユーザーが借りると予約を使い終え、次のユーザーが先頭に繰り上がる
+        repo.create(CreateCheckout::new(book_id1, user_id1, Utc::now()))
+            .await?;
+        let queue = reservation_repo.find_queue(book_id1).await?;
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].reserved_by.id, user_id2);
+        assert_eq!(queue[0].position, 1);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_daily_counts(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 11/1 に2件（うち1件は返却済み）、11/3 に1件、11/6 に範囲外の1件の貸出を用意する
+        sqlx::query(
+            r#"
+                INSERT INTO returned_checkouts (checkout_id, book_id, user_id, checked_out_at, returned_at)
+                VALUES
+                (gen_random_uuid(), '9890736e-a4e4-461a-a77d-eac3517ef11b', '9582f9de-0fd1-4892-b20c-70139a7eb95b', '2024-11-01 09:00:00+00', '2024-11-02 09:00:00+00'),
+                (gen_random_uuid(), '9890736e-a4e4-461a-a77d-eac3517ef11b', '050afe56-c3da-4448-8e4d-6f44007d2ca5', '2024-11-03 23:59:59+00', '2024-11-05 09:00:00+00'),
+                (gen_random_uuid(), '9890736e-a4e4-461a-a77d-eac3517ef11b', '050afe56-c3da-4448-8e4d-6f44007d2ca5', '2024-11-06 00:00:00+00', '2024-11-07 09:00:00+00');
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            r#"
+                INSERT INTO checkouts (book_id, user_id, checked_out_at, due_at)
+                VALUES ('1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11', '9582f9de-0fd1-4892-b20c-70139a7eb95b', '2024-11-01 15:00:00+00', '2024-11-15 15:00:00+00');
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let (repo, ..) = init_repo(pool);
+
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 11, d).unwrap();
+        let res = repo.daily_counts(date(1), date(5)).await?;
+        let counts: Vec<(NaiveDate, i64)> = res.into_iter().map(|c| (c.date, c.count)).collect();
+        assert_eq!(
+            counts,
+            vec![
+                (date(1), 2),
+                (date(2), 0),
+                (date(3), 1),
+                (date(4), 0),
+                (date(5), 0),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_history_survives_book_deletion(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        use crate::repository::book::BookRepositoryImpl;
+        use kernel::{model::book::event::DeleteBook, repository::book::BookRepository};
+
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let (repo, user_id1, _, book_id1) = init_repo(pool);
+        // 管理者のID（fixtures/common.sql参照）
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        repo.create(CreateCheckout {
+            book_id: book_id1,
+            checked_out_by: user_id1,
+            checked_out_at: Utc::now(),
+        })
+        .await?;
+        let checkout_id = repo.find_unreturned_by_book_id(book_id1).await?.unwrap().id;
+        repo.update_returned(UpdateReturned {
+            checkout_id,
+            book_id: book_id1,
+            returned_by: user_id1,
+            returned_at: Utc::now(),
+        })
+        .await?;
+
+        book_repo
+            .delete(DeleteBook {
+                book_id: book_id1,
+                requested_user: admin_id,
+            })
+            .await?;
+
+        // 削除した蔵書は一覧から消え、貸し出しもできない
+        let books = book_repo.find_all(options()).await?;
+        assert!(books.items.iter().all(|book| book.id != book_id1));
+        let res = repo
+            .create(CreateCheckout {
+                book_id: book_id1,
+                checked_out_by: user_id1,
+                checked_out_at: Utc::now(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 貸出履歴は残っている
+        let history = repo.find_history_by_book_id(book_id1, options()).await?;
+        assert_eq!(history.total, Some(1));
+        assert_eq!(history.items[0].id, checkout_id);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::{
+    favorite::event::{CreateFavorite, DeleteFavorite},
+    id::{BookId, UserId},
+};
+use kernel::repository::favorite::FavoriteRepository;
+use shared::error::{AppError, AppResult};
+
+use crate::database::{map_db_error, ConnectionPool};
+
+#[derive(new)]
+pub struct FavoriteRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl FavoriteRepository for FavoriteRepositoryImpl {
+    // お気に入りに登録する。登録済みの場合は何もしない
+    async fn create(&self, event: CreateFavorite) -> AppResult<()> {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+            event.book_id as _
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        if !exists {
+            return Err(AppError::not_found("Book", event.book_id));
+        }
+
+        sqlx::query!(
+            r#"
+                INSERT INTO favorites (user_id, book_id)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                ;
+            "#,
+            event.user_id as _,
+            event.book_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(())
+    }
+
+    // お気に入りから外す。登録されていない場合も成功扱いとする
+    async fn delete(&self, event: DeleteFavorite) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+                DELETE FROM favorites
+                WHERE user_id = $1
+                AND book_id = $2
+                ;
+            "#,
+            event.user_id as _,
+            event.book_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(())
+    }
+
+    // 指定した蔵書のうち、ユーザーがお気に入りに登録しているものの ID を返す
+    async fn find_favorite_book_ids(
+        &self,
+        user_id: UserId,
+        book_ids: &[BookId],
+    ) -> AppResult<HashSet<BookId>> {
+        let rows = sqlx::query_scalar!(
+            r#"
+                SELECT book_id AS "book_id: BookId"
+                FROM favorites
+                WHERE user_id = $1
+                AND book_id = ANY($2)
+                ;
+            "#,
+            user_id as _,
+            book_ids as _,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(rows.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::book::BookRepositoryImpl;
+    use kernel::{model::book::BookListOptions, repository::book::BookRepository};
+    use std::str::FromStr;
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_favorite(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = FavoriteRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 事前登録したユーザー＆蔵書のID（fixtures/common.sql, fixtures/book.sql参照）
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let options = || BookListOptions {
+            limit: 20,
+            offset: 0,
+            ..Default::default()
+        };
+
+        // 存在しない蔵書はお気に入りに登録できない
+        let res = repo
+            .create(CreateFavorite::new(BookId::new(), user_id))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 二重に登録しても1件のまま
+        repo.create(CreateFavorite::new(book_id, user_id)).await?;
+        repo.create(CreateFavorite::new(book_id, user_id)).await?;
+
+        let res = book_repo.find_favorites(user_id, options()).await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(res.items[0].id, book_id);
+
+        let ids = repo
+            .find_favorite_book_ids(user_id, &[book_id, BookId::new()])
+            .await?;
+        assert_eq!(ids, HashSet::from([book_id]));
+
+        // 削除後は一覧に含まれない。未登録の削除も失敗しない
+        repo.delete(DeleteFavorite::new(book_id, user_id)).await?;
+        repo.delete(DeleteFavorite::new(book_id, user_id)).await?;
+
+        let res = book_repo.find_favorites(user_id, options()).await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        Ok(())
+    }
+}
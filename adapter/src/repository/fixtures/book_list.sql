@@ -0,0 +1,11 @@
+INSERT INTO
+    books (title, author, isbn, description, user_id, created_at)
+SELECT
+    'title' || LPAD(i::TEXT, 3, '0'),
+    'author' || LPAD(i::TEXT, 3, '0'),
+    'isbn' || LPAD(i::TEXT, 3, '0'),
+    'description' || LPAD(i::TEXT, 3, '0'),
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c',
+    TIMESTAMP WITH TIME ZONE '2024-01-01 00:00:00+00' + (i || ' minutes')::INTERVAL
+FROM
+    generate_series(1, 50) AS i;
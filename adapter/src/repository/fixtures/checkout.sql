@@ -0,0 +1,45 @@
+INSERT INTO
+    users (user_id, name, email, password_hash, role_id)
+SELECT
+    '9582f9de-0fd1-4892-b20c-70139a7eb95b',
+    'Adam Smith',
+    'adam.smith@example.com',
+    '$2b$12$GFf.eB7OpIcB3hpCr/JhoOOVPHQ0YE9oLnDA0KyHq7oGBvAFospLK',
+    role_id
+FROM
+    roles
+WHERE
+    name LIKE 'User';
+
+INSERT INTO
+    users (user_id, name, email, password_hash, role_id)
+SELECT
+    '050afe56-c3da-4448-8e4d-6f44007d2ca5',
+    'Bob Carter',
+    'bob.carter@example.com',
+    '$2b$12$GFf.eB7OpIcB3hpCr/JhoOOVPHQ0YE9oLnDA0KyHq7oGBvAFospLK',
+    role_id
+FROM
+    roles
+WHERE
+    name LIKE 'User';
+
+INSERT INTO
+    books (book_id, title, author, isbn, description, user_id)
+VALUES
+    (
+        '9890736e-a4e4-461a-a77d-eac3517ef11b',
+        '実践Rustプログラミング入門',
+        '初田直也他',
+        '978-4798061702',
+        'C/C++の代わりとなるべき最新言語その独特な仕様をわかりやすく解説。',
+        '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c'
+    ),
+    (
+        '1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11',
+        'プログラミングRust 第2版',
+        'Jim Blandy他',
+        '978-4873119786',
+        'Rustの基本から高度なトピックまでを網羅した解説書。',
+        '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c'
+    );
@@ -0,0 +1,19 @@
+INSERT INTO
+    roles (name)
+VALUES
+    ('Admin'),
+    ('User')
+ON CONFLICT DO NOTHING;
+
+INSERT INTO
+    users (user_id, name, email, password_hash, role_id)
+SELECT
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c',
+    'Eleazar Fig',
+    'eleazar.fig@example.com',
+    '$2b$12$GFf.eB7OpIcB3hpCr/JhoOOVPHQ0YE9oLnDA0KyHq7oGBvAFospLK',
+    role_id
+FROM
+    roles
+WHERE
+    name LIKE 'Admin';
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use derive_new::new;
-use kernel::repository::health::HealthCheckRepository;
+use kernel::repository::health::{DependencyCheck, HealthCheckRepository};
+use shared::error::{AppError, AppResult};
 
 use crate::database::ConnectionPool;
 
@@ -11,10 +12,73 @@ pub struct HealthCheckRepositoryImpl {
 
 #[async_trait]
 impl HealthCheckRepository for HealthCheckRepositoryImpl {
-    async fn check_db(&self) -> bool {
+    async fn check_db(&self) -> AppResult<()> {
+        sqlx::query("SELECT 1")
+            .fetch_one(self.db.inner_ref())
+            .await
+            .map(|_| ())
+            .map_err(AppError::SpecificOperationError)
+    }
+}
+
+#[derive(new)]
+pub struct DatabaseDependencyCheck {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl DependencyCheck for DatabaseDependencyCheck {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+
+    async fn is_ready(&self) -> bool {
         sqlx::query("SELECT 1")
             .fetch_one(self.db.inner_ref())
             .await
             .is_ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+    use super::*;
+
+    #[sqlx::test]
+    async fn test_database_dependency_check(pool: sqlx::PgPool) {
+        let check = DatabaseDependencyCheck::new(ConnectionPool::new(pool));
+        assert_eq!(check.name(), "database");
+        assert!(check.is_ready().await);
+    }
+
+    #[tokio::test]
+    async fn test_database_dependency_check_down() {
+        let check = DatabaseDependencyCheck::new(unreachable_pool());
+        assert!(!check.is_ready().await);
+    }
+
+    #[sqlx::test]
+    async fn test_check_db(pool: sqlx::PgPool) {
+        let repo = HealthCheckRepositoryImpl::new(ConnectionPool::new(pool));
+        assert!(repo.check_db().await.is_ok());
+
+        let repo = HealthCheckRepositoryImpl::new(unreachable_pool());
+        assert!(matches!(
+            repo.check_db().await,
+            Err(AppError::SpecificOperationError(_))
+        ));
+    }
+
+    // 何も待ち受けていないポートに接続しようとするプール
+    fn unreachable_pool() -> ConnectionPool {
+        let options = PgConnectOptions::new().host("127.0.0.1").port(1);
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(1))
+            .connect_lazy_with(options);
+        ConnectionPool::new(pool)
+    }
+}
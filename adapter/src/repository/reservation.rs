@@ -0,0 +1,247 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::{
+    id::{BookId, ReservationId},
+    reservation::{
+        event::{CreateReservation, DeleteReservation},
+        Reservation, ReservationPosition,
+    },
+};
+use kernel::repository::reservation::ReservationRepository;
+use shared::error::{AppError, AppResult};
+use sqlx::PgConnection;
+
+use crate::database::{
+    ensure_affected, map_db_error,
+    model::reservation::{ReservationPositionRow, ReservationRow},
+    ConnectionPool,
+};
+
+#[derive(new)]
+pub struct ReservationRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl ReservationRepository for ReservationRepositoryImpl {
+    // 蔵書を予約し、順番待ちの位置を返す。
+    // 同じユーザーが予約済みの場合は新たに予約せず、既存の予約の位置を返す
+    async fn create(&self, event: CreateReservation) -> AppResult<ReservationPosition> {
+        let mut tx = self.db.begin().await?;
+
+        ensure_book_exists(&mut tx, event.book_id).await?;
+
+        // 同時に二重で予約された場合も、ユニークインデックスにより片方だけが登録される
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO reservations (reservation_id, book_id, user_id)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (book_id, user_id) DO NOTHING
+                ;
+            "#,
+            ReservationId::new() as _,
+            event.book_id as _,
+            event.user_id as _,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+        let created = res.rows_affected() > 0;
+
+        // 予約を受け付けた順に並べたときの位置を求める
+        let row = sqlx::query_as!(
+            ReservationPositionRow,
+            r#"
+                SELECT
+                q.reservation_id AS "reservation_id!: ReservationId",
+                q.position AS "position!"
+                FROM (
+                    SELECT
+                    reservation_id,
+                    user_id,
+                    ROW_NUMBER() OVER (ORDER BY queued_order) AS position
+                    FROM reservations
+                    WHERE book_id = $1
+                ) AS q
+                WHERE q.user_id = $2
+                ;
+            "#,
+            event.book_id as _,
+            event.user_id as _,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(ReservationPosition {
+            reservation_id: row.reservation_id,
+            book_id: event.book_id,
+            position: row.position,
+            created,
+        })
+    }
+
+    // 蔵書の順番待ちを、予約を受け付けた順に返す
+    async fn find_queue(&self, book_id: BookId) -> AppResult<Vec<Reservation>> {
+        let mut conn = self.db.inner_ref().acquire().await.map_err(map_db_error)?;
+
+        ensure_book_exists(&mut conn, book_id).await?;
+
+        let rows = sqlx::query_as!(
+            ReservationRow,
+            r#"
+                SELECT
+                r.reservation_id,
+                r.book_id,
+                r.user_id,
+                u.name AS user_name,
+                ROW_NUMBER() OVER (ORDER BY r.queued_order) AS "position!",
+                r.created_at
+                FROM reservations AS r
+                INNER JOIN users AS u USING(user_id)
+                WHERE r.book_id = $1
+                ORDER BY r.queued_order
+                ;
+            "#,
+            book_id as _,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(rows.into_iter().map(Reservation::from).collect())
+    }
+
+    // 自分の予約を取り消す。後ろに並んでいた予約は順番が 1 つずつ繰り上がる
+    async fn cancel(&self, event: DeleteReservation) -> AppResult<()> {
+        let res = sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE book_id = $1
+                AND user_id = $2
+                ;
+            "#,
+            event.book_id as _,
+            event.user_id as _,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        ensure_affected(
+            &res,
+            AppError::not_found("Reservation for book", event.book_id),
+        )
+    }
+}
+
+// 削除済みの蔵書は予約も順番待ちの参照もできない
+async fn ensure_book_exists(conn: &mut PgConnection, book_id: BookId) -> AppResult<()> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+        book_id as _
+    )
+    .fetch_one(conn)
+    .await
+    .map_err(map_db_error)?;
+
+    if !exists {
+        return Err(AppError::not_found("Book", book_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::id::{BookId, UserId};
+    use std::str::FromStr;
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_reserve(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool));
+
+        // 事前登録したユーザー＆蔵書のID（fixtures/common.sql, fixtures/checkout.sql参照）
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let user_id1 = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let user_id2 = UserId::from_str("050afe56-c3da-4448-8e4d-6f44007d2ca5")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        // 存在しない蔵書は予約できない
+        let res = repo
+            .create(CreateReservation::new(BookId::new(), user_id1))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 予約した順に位置が決まる
+        let first = repo
+            .create(CreateReservation::new(book_id, user_id1))
+            .await?;
+        assert_eq!((first.position, first.created), (1, true));
+        let second = repo
+            .create(CreateReservation::new(book_id, user_id2))
+            .await?;
+        assert_eq!((second.position, second.created), (2, true));
+
+        // 同じユーザーが再度予約しても、既存の予約と位置が返る
+        let repeat = repo
+            .create(CreateReservation::new(book_id, user_id1))
+            .await?;
+        assert_eq!(repeat.reservation_id, first.reservation_id);
+        assert_eq!((repeat.position, repeat.created), (1, false));
+
+        let third = repo
+            .create(CreateReservation::new(book_id, admin_id))
+            .await?;
+        assert_eq!(third.position, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_queue_and_cancel(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = ReservationRepositoryImpl::new(ConnectionPool::new(pool));
+
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let user_id1 = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let user_id2 = UserId::from_str("050afe56-c3da-4448-8e4d-6f44007d2ca5")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        let res = repo.find_queue(BookId::new()).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(repo.find_queue(book_id).await?.is_empty());
+
+        for user_id in [user_id1, user_id2, admin_id] {
+            repo.create(CreateReservation::new(book_id, user_id))
+                .await?;
+        }
+        let queue = repo.find_queue(book_id).await?;
+        assert_eq!(
+            queue
+                .iter()
+                .map(|r| (r.reserved_by.id, r.position))
+                .collect::<Vec<_>>(),
+            vec![(user_id1, 1), (user_id2, 2), (admin_id, 3)]
+        );
+
+        // 取り消すと後ろの予約が繰り上がる
+        repo.cancel(DeleteReservation::new(book_id, user_id1))
+            .await?;
+        let queue = repo.find_queue(book_id).await?;
+        assert_eq!(
+            queue
+                .iter()
+                .map(|r| (r.reserved_by.id, r.position))
+                .collect::<Vec<_>>(),
+            vec![(user_id2, 1), (admin_id, 2)]
+        );
+
+        // 予約していない場合は取り消せない
+        let res = repo.cancel(DeleteReservation::new(book_id, user_id1)).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+}
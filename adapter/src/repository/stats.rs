@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{model::stats::LibraryStats, repository::stats::StatsRepository};
+use shared::error::AppResult;
+
+use crate::database::{map_db_error, ConnectionPool};
+
+#[derive(new)]
+pub struct StatsRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl StatsRepository for StatsRepositoryImpl {
+    // 各テーブルの件数を 1 回の問い合わせでまとめて取得する
+    async fn library_stats(&self) -> AppResult<LibraryStats> {
+        sqlx::query_as!(
+            LibraryStats,
+            r#"
+                SELECT
+                (SELECT COUNT(*) FROM books WHERE deleted_at IS NULL) AS "total_books!",
+                (SELECT COUNT(*) FROM checkouts) AS "active_checkouts!",
+                (SELECT COUNT(*) FROM users) AS "total_users!"
+                ;
+            "#
+        )
+        .fetch_one(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_library_stats(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = StatsRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let before = repo.library_stats().await?;
+
+        // 貸出中の件数に数え、削除済みの蔵書は数えない
+        sqlx::query!(
+            r#"
+            INSERT INTO checkouts (book_id, user_id, due_at)
+            SELECT book_id, user_id, CURRENT_TIMESTAMP(3)
+            FROM books
+            LIMIT 1
+            "#
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE books SET deleted_at = CURRENT_TIMESTAMP(3)
+            WHERE book_id = (SELECT book_id FROM books LIMIT 1)
+            "#
+        )
+        .execute(&pool)
+        .await?;
+
+        let after = repo.library_stats().await?;
+        assert_eq!(
+            after,
+            LibraryStats {
+                total_books: before.total_books - 1,
+                active_checkouts: before.active_checkouts + 1,
+                total_users: before.total_users,
+            }
+        );
+        assert!(after.total_users > 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::tag::{
+    event::{AttachTag, DetachTag},
+    Tag,
+};
+use kernel::repository::tag::TagRepository;
+use shared::error::{AppError, AppResult};
+
+use crate::database::{map_db_error, ConnectionPool};
+
+#[derive(new)]
+pub struct TagRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl TagRepository for TagRepositoryImpl {
+    // 蔵書にタグを付ける。未登録のタグは作成し、付与済みの場合は何もしない
+    async fn attach(&self, event: AttachTag) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM books WHERE book_id = $1 AND deleted_at IS NULL) AS "exists!""#,
+            event.book_id as _
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+
+        if !exists {
+            return Err(AppError::not_found("Book", event.book_id));
+        }
+
+        // 同時に同じ名前のタグを作成しても重複しないよう、ON CONFLICT で既存の行を使う
+        sqlx::query!(
+            r#"
+                INSERT INTO tags (name)
+                VALUES ($1)
+                ON CONFLICT (name) DO NOTHING
+                ;
+            "#,
+            event.name,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+
+        sqlx::query!(
+            r#"
+                INSERT INTO book_tags (book_id, tag_id)
+                SELECT $1, tag_id FROM tags WHERE name = $2
+                ON CONFLICT DO NOTHING
+                ;
+            "#,
+            event.book_id as _,
+            event.name,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    // 蔵書からタグを外す。付いていない場合も成功扱いとする
+    async fn detach(&self, event: DetachTag) -> AppResult<()> {
+        sqlx::query!(
+            r#"
+                DELETE FROM book_tags AS bt
+                USING tags AS t
+                WHERE bt.tag_id = t.tag_id
+                AND bt.book_id = $1
+                AND t.name = $2
+                ;
+            "#,
+            event.book_id as _,
+            event.name,
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(())
+    }
+
+    // すべてのタグを名前の昇順に返す。蔵書が外されたタグも件数 0 で含める
+    async fn list(&self) -> AppResult<Vec<Tag>> {
+        let tags = sqlx::query_as!(
+            Tag,
+            r#"
+                SELECT
+                    t.name,
+                    COUNT(b.book_id) AS "book_count!"
+                FROM tags AS t
+                LEFT JOIN book_tags AS bt USING(tag_id)
+                LEFT JOIN books AS b ON b.book_id = bt.book_id AND b.deleted_at IS NULL
+                GROUP BY t.tag_id, t.name
+                ORDER BY t.name
+                ;
+            "#,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::book::BookRepositoryImpl;
+    use kernel::{
+        model::{
+            book::{Book, BookListOptions, TagMatch},
+            id::BookId,
+            list::PaginatedList,
+        },
+        repository::book::BookRepository,
+    };
+    use std::{collections::HashSet, str::FromStr};
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_tags(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = TagRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 事前登録した蔵書のID（fixtures/book.sql参照）
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let options = |tag: &str| BookListOptions {
+            limit: 20,
+            offset: 0,
+            tags: vec![tag.into()],
+            ..Default::default()
+        };
+
+        // 存在しない蔵書にはタグを付けられない
+        let res = repo
+            .attach(AttachTag::new(BookId::new(), "rust".into()))
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 二重に付けても1件のまま。蔵書のタグは名前の昇順に並ぶ
+        repo.attach(AttachTag::new(book_id, "rust".into())).await?;
+        repo.attach(AttachTag::new(book_id, "rust".into())).await?;
+        repo.attach(AttachTag::new(book_id, "programming".into()))
+            .await?;
+
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.tags, vec!["programming", "rust"]);
+
+        let res = book_repo.find_all(options("rust")).await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(res.items[0].id, book_id);
+        assert_eq!(res.items[0].tags, vec!["programming", "rust"]);
+        let res = book_repo.find_all(options("novel")).await?;
+        assert_eq!(res.total, Some(0));
+
+        let tags = repo.list().await?;
+        let tags: Vec<_> = tags
+            .iter()
+            .map(|t| (t.name.as_str(), t.book_count))
+            .collect();
+        assert_eq!(tags, vec![("programming", 1), ("rust", 1)]);
+
+        // 外したタグは蔵書に含まれず、絞り込みにも該当しない。付いていないタグを外しても失敗しない
+        repo.detach(DetachTag::new(book_id, "rust".into())).await?;
+        repo.detach(DetachTag::new(book_id, "rust".into())).await?;
+
+        let book = book_repo.find_by_id(book_id).await?.unwrap();
+        assert_eq!(book.tags, vec!["programming"]);
+        let res = book_repo.find_all(options("rust")).await?;
+        assert_eq!(res.total, Some(0));
+        assert!(res.items.is_empty());
+
+        // 蔵書が外されたタグも件数 0 で一覧に残る
+        let tags = repo.list().await?;
+        let tags: Vec<_> = tags
+            .iter()
+            .map(|t| (t.name.as_str(), t.book_count))
+            .collect();
+        assert_eq!(tags, vec![("programming", 1), ("rust", 0)]);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book_list"))]
+    async fn test_filter_by_tags(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = TagRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let books = book_repo
+            .find_all(BookListOptions {
+                limit: 3,
+                offset: 0,
+                ..Default::default()
+            })
+            .await?
+            .items;
+        let (both, scifi, classic) = (books[0].id, books[1].id, books[2].id);
+        for (book_id, name) in [
+            (both, "scifi"),
+            (both, "classic"),
+            (scifi, "scifi"),
+            (classic, "classic"),
+        ] {
+            repo.attach(AttachTag::new(book_id, name.into())).await?;
+        }
+
+        let find = |tags: &[&str], tag_match: TagMatch, limit: i64, skip_total: bool| {
+            book_repo.find_all(BookListOptions {
+                limit,
+                offset: 0,
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                tag_match,
+                skip_total,
+                ..Default::default()
+            })
+        };
+        let ids = |list: &PaginatedList<Book>| -> HashSet<BookId> {
+            list.items.iter().map(|b| b.id).collect()
+        };
+
+        // all はすべてのタグが付いた蔵書だけを返す。同じタグを重複して指定しても結果は変わらない
+        let res = find(&["scifi", "classic"], TagMatch::All, 20, false).await?;
+        assert_eq!(res.total, Some(1));
+        assert_eq!(ids(&res), HashSet::from([both]));
+        let res = find(&["scifi", "classic", "scifi"], TagMatch::All, 20, false).await?;
+        assert_eq!(res.total, Some(1));
+
+        // any はいずれかのタグが付いた蔵書を返す
+        let res = find(&["scifi", "classic"], TagMatch::Any, 20, false).await?;
+        assert_eq!(res.total, Some(3));
+        assert_eq!(ids(&res), HashSet::from([both, scifi, classic]));
+
+        // ページを絞っても total は絞り込み後の件数になる
+        let res = find(&["scifi", "classic"], TagMatch::Any, 1, false).await?;
+        assert_eq!(res.total, Some(3));
+        assert_eq!(res.items.len(), 1);
+
+        // 総件数を省略するクエリでも同じように絞り込む
+        let res = find(&["scifi", "classic"], TagMatch::All, 20, true).await?;
+        assert_eq!(res.total, None);
+        assert_eq!(ids(&res), HashSet::from([both]));
+        let res = find(&["scifi", "classic"], TagMatch::Any, 20, true).await?;
+        assert_eq!(ids(&res), HashSet::from([both, scifi, classic]));
+
+        // 存在しないタグを含む場合、all では該当なし、any では他のタグで絞り込む
+        let res = find(&["scifi", "unknown"], TagMatch::All, 20, false).await?;
+        assert_eq!(res.total, Some(0));
+        let res = find(&["scifi", "unknown"], TagMatch::Any, 20, false).await?;
+        assert_eq!(res.total, Some(2));
+
+        // タグを指定しなければ絞り込まない
+        let res = find(&[], TagMatch::All, 20, false).await?;
+        assert_eq!(res.total, Some(50));
+
+        Ok(())
+    }
+}
@@ -1,24 +1,47 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use derive_new::new;
-use kernel::model::id::UserId;
+use garde::Validate;
+use kernel::model::id::{BookId, UserId};
 use kernel::model::user::{
     event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
-    User,
+    User, UserDetail,
 };
 use kernel::repository::user::UserRepository;
 use shared::error::{AppError, AppResult};
 
-use crate::database::{model::user::UserRow, ConnectionPool};
+use crate::database::{
+    ensure_affected, map_db_error,
+    model::user::{UserDetailRow, UserRow},
+    ConnectionPool,
+};
+use crate::redis::RedisClient;
+use crate::repository::book::invalidate_book_cache;
 use kernel::model::role::Role;
 
 #[derive(new)]
 pub struct UserRepositoryImpl {
     db: ConnectionPool,
+    // 蔵書をキャッシュしている場合は、ユーザーの削除で消えた蔵書のキャッシュも削除する
+    #[new(default)]
+    book_cache: Option<Arc<RedisClient>>,
+}
+
+impl UserRepositoryImpl {
+    pub fn with_book_cache(mut self, kv: Arc<RedisClient>) -> Self {
+        self.book_cache = Some(kv);
+        self
+    }
 }
 
 #[async_trait]
 impl UserRepository for UserRepositoryImpl {
     async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>> {
+        self.find_by_id(current_user_id).await
+    }
+
+    async fn find_by_id(&self, user_id: UserId) -> AppResult<Option<User>> {
         let row = sqlx::query_as!(
             UserRow,
             r#"
@@ -27,17 +50,60 @@ impl UserRepository for UserRepositoryImpl {
       INNER JOIN roles AS r USING(role_id)
       WHERE user_id = $1
       "#,
-            current_user_id as _,
+            user_id as _,
         )
         .fetch_optional(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
         match row {
             Some(r) => Ok(Some(User::try_from(r)?)),
             None => Ok(None),
         }
     }
+
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>> {
+        let row = sqlx::query_as!(
+            UserRow,
+            r#"
+      SELECT u.user_id, u.name, u.email, r.name as role_name, created_at, updated_at
+      FROM users AS u
+      INNER JOIN roles AS r USING(role_id)
+      WHERE LOWER(u.email) = LOWER($1)
+      "#,
+            email,
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        row.map(User::try_from).transpose()
+    }
+
+    // 貸出中の冊数は、返却されると checkouts から行が消えるため件数をそのまま数える
+    async fn find_detail_by_id(&self, user_id: UserId) -> AppResult<Option<UserDetail>> {
+        let row = sqlx::query_as!(
+            UserDetailRow,
+            r#"
+      SELECT
+        u.user_id,
+        u.name,
+        u.email,
+        r.name as role_name,
+        (SELECT COUNT(*) FROM checkouts AS c WHERE c.user_id = u.user_id) AS "active_checkout_count!"
+      FROM users AS u
+      INNER JOIN roles AS r USING(role_id)
+      WHERE user_id = $1
+      "#,
+            user_id as _,
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?;
+
+        row.map(UserDetail::try_from).transpose()
+    }
+
     async fn find_all(&self) -> AppResult<Vec<User>> {
         let users = sqlx::query_as!(
             UserRow,
@@ -50,7 +116,7 @@ impl UserRepository for UserRepositoryImpl {
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
+        .map_err(map_db_error)?
         .into_iter()
         .filter_map(|r| User::try_from(r).ok())
         .collect();
@@ -60,6 +126,7 @@ impl UserRepository for UserRepositoryImpl {
 
     async fn create(&self, event: CreateUser) -> AppResult<User> {
         let user_id = UserId::new();
+        let email = normalize_email(&event.email)?;
         let hashed_password = hash_password(&event.password)?;
         let role = Role::User;
 
@@ -70,23 +137,29 @@ impl UserRepository for UserRepositoryImpl {
         "#,
             user_id as _,
             event.name,
-            event.email,
+            email,
             hashed_password,
             role.as_ref()
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(|e| match e.as_database_error() {
+            // email には一意制約があるため、登録済みのメールアドレスは一意制約違反になる
+            Some(db) if db.is_unique_violation() => AppError::EmailAlreadyExists(format!(
+                "メールアドレス（{}）は既に登録されています。",
+                email
+            )),
+            _ => map_db_error(e),
+        })?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No user has been created".into(),
-            ));
-        }
+        ensure_affected(
+            &res,
+            AppError::NoRowsAffectedError("No user has been created".into()),
+        )?;
         Ok(User {
             id: user_id,
             name: event.name,
-            email: event.email,
+            email,
             role,
         })
     }
@@ -102,7 +175,7 @@ impl UserRepository for UserRepositoryImpl {
         )
         .fetch_one(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?
+        .map_err(map_db_error)?
         .password_hash;
 
         verify_password(&event.current_password, &original_password_hash)?;
@@ -117,59 +190,127 @@ impl UserRepository for UserRepositoryImpl {
         )
         .execute(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        tx.commit().await.map_err(AppError::TransactionError);
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
 
+    // ロールは roles テーブルに登録されているものだけを受け付ける
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+        let role_id = sqlx::query_scalar!(
+            r#"
+        SELECT role_id FROM roles WHERE name = $1
+        "#,
+            event.role.as_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| AppError::invalid_field("role", "unknown role"))?;
+
         let res = sqlx::query!(
             r#"
         UPDATE users
-        SET role_id = (
-          SELECT role_id FROM roles WHERE name = $2
-        )
+        SET role_id = $2
         WHERE user_id = $1
         "#,
             event.user_id as _,
-            event.role.as_ref()
+            role_id,
         )
         .execute(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound(
-                "Specified user does not exist".into(),
-            ));
-        }
-
-        Ok(())
+        ensure_affected(&res, AppError::not_found("User", event.user_id))
     }
 
     async fn delete(&self, event: DeleteUser) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // ユーザーを削除すると、所有する蔵書とその貸出も連鎖して削除される。
+        // 削除の途中で貸出が登録されないよう、ユーザーと所有する蔵書の行をロックしてから貸出を確認する
+        let book_ids = sqlx::query_scalar!(
+            r#"
+        SELECT book_id AS "book_id: BookId" FROM books WHERE user_id = $1 FOR UPDATE
+        "#,
+            event.user_id as _
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(map_db_error)?;
+
+        let row = sqlx::query!(
+            r#"
+        SELECT
+            EXISTS(SELECT 1 FROM checkouts AS c WHERE c.user_id = u.user_id) AS "has_checkouts!",
+            EXISTS(
+                SELECT 1 FROM checkouts AS c
+                INNER JOIN books AS b USING(book_id)
+                WHERE b.user_id = u.user_id
+            ) AS "has_lent_books!"
+        FROM users AS u
+        WHERE u.user_id = $1
+        FOR UPDATE
+        "#,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(map_db_error)?
+        .ok_or_else(|| AppError::not_found("User", event.user_id))?;
+
+        if row.has_checkouts {
+            return Err(AppError::Conflict(
+                "貸出中の蔵書があるユーザーは削除できません。".into(),
+            ));
+        }
+        // 他のユーザーの貸出が記録なしに消えないよう、所有する蔵書が貸出中の間も削除できない
+        if row.has_lent_books {
+            return Err(AppError::Conflict(
+                "所有する蔵書が貸出中のユーザーは削除できません。".into(),
+            ));
+        }
+
         let res = sqlx::query!(
             r#"
         DELETE FROM users WHERE user_id = $1
         "#,
             event.user_id as _
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(map_db_error)?;
+        ensure_affected(&res, AppError::not_found("User", event.user_id))?;
 
-        if res.rows_affected() < 1 {
-            return Err(AppError::EntityNotFound(
-                "Specified user does not exist".into(),
-            ));
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        if let Some(kv) = &self.book_cache {
+            for book_id in book_ids {
+                invalidate_book_cache(kv, book_id).await;
+            }
         }
 
         Ok(())
     }
 }
 
+#[derive(Validate)]
+struct NormalizedEmail {
+    #[garde(email)]
+    email: String,
+}
+
+// 大文字・小文字だけが異なるアカウントを作らないよう、前後の空白を除いて小文字に揃える
+fn normalize_email(email: &str) -> AppResult<String> {
+    let normalized = NormalizedEmail {
+        email: email.trim().to_lowercase(),
+    };
+    normalized.validate(&())?;
+    Ok(normalized.email)
+}
+
 fn hash_password(password: &str) -> AppResult<String> {
     bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(AppError::from)
 }
@@ -181,3 +322,315 @@ fn verify_password(password: &str, hash: &str) -> AppResult<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::Utc;
+    use kernel::model::{
+        checkout::event::{CreateCheckout, UpdateReturned},
+        id::BookId,
+    };
+    use kernel::repository::checkout::CheckoutRepository;
+
+    use super::*;
+    use crate::repository::checkout::CheckoutRepositoryImpl;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_hashes_password(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user = repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test.user@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+
+        // 平文のパスワードは保存せず、bcrypt のハッシュだけを保存する
+        let stored: String =
+            sqlx::query_scalar("SELECT password_hash FROM users WHERE user_id = $1")
+                .bind(user.id.raw())
+                .fetch_one(&pool)
+                .await?;
+        assert_ne!(stored, "test_password");
+        assert!(verify_password("test_password", &stored).is_ok());
+        assert!(matches!(
+            verify_password("wrong_password", &stored),
+            Err(AppError::UnauthenticatedError)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_find_by_id(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool));
+        // 事前登録した管理者ユーザー（fixtures/common.sql参照）
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        let user = repo.find_by_id(admin_id).await?.unwrap();
+        assert_eq!(user.name, "Eleazar Fig");
+        assert_eq!(user.role, Role::Admin);
+
+        assert!(repo.find_by_id(UserId::new()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_find_by_email(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool));
+        let admin_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+
+        // 大文字・小文字の違いは同じアカウントとして扱う
+        for email in ["eleazar.fig@example.com", "Eleazar.Fig@Example.COM"] {
+            let user = repo.find_by_email(email).await?.unwrap();
+            assert_eq!(user.id, admin_id);
+            assert_eq!(user.role, Role::Admin);
+        }
+
+        assert!(repo.find_by_email("nobody@example.com").await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_find_detail_by_id(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+
+        let detail = repo.find_detail_by_id(user_id).await?.unwrap();
+        assert_eq!(detail.user.id, user_id);
+        assert_eq!(detail.active_checkout_count, 0);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO checkouts (book_id, user_id, due_at)
+            SELECT book_id, $1, CURRENT_TIMESTAMP(3)
+            FROM books
+            LIMIT 2
+            "#,
+            user_id as _,
+        )
+        .execute(&pool)
+        .await?;
+
+        let detail = repo.find_detail_by_id(user_id).await?.unwrap();
+        assert_eq!(detail.active_checkout_count, 2);
+
+        assert!(repo.find_detail_by_id(UserId::new()).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_duplicate_email(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool));
+        let create_user = || CreateUser {
+            name: "Test User".into(),
+            email: "test.user@example.com".into(),
+            password: "test_password".into(),
+        };
+
+        repo.create(create_user()).await?;
+        let res = repo.create(create_user()).await;
+        assert!(matches!(res, Err(AppError::EmailAlreadyExists(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_normalizes_email(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool));
+        let create_user = |email: &str| CreateUser {
+            name: "Test User".into(),
+            email: email.into(),
+            password: "test_password".into(),
+        };
+
+        let user = repo.create(create_user(" A@B.com ")).await?;
+        assert_eq!(user.email, "a@b.com");
+        let stored = repo.find_by_id(user.id).await?.unwrap();
+        assert_eq!(stored.email, "a@b.com");
+
+        // 大文字・小文字だけが異なるメールアドレスは重複とみなす
+        let res = repo.create(create_user("a@b.com")).await;
+        assert!(matches!(res, Err(AppError::EmailAlreadyExists(_))));
+
+        for email in ["   ", "not-an-email"] {
+            let res = repo.create(create_user(email)).await;
+            assert!(matches!(res, Err(AppError::ValidationError(_))));
+        }
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_delete_with_active_checkout(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 3, 14);
+        // 事前登録したユーザー＆蔵書のID（fixtures/checkout.sql参照）
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        checkout_repo
+            .create(CreateCheckout::new(book_id, user_id, Utc::now()))
+            .await?;
+
+        // 貸出中の蔵書があるうちは削除できない
+        let res = repo.delete(DeleteUser { user_id }).await;
+        assert!(matches!(res, Err(AppError::Conflict(_))));
+        assert!(repo.find_by_id(user_id).await?.is_some());
+
+        // 返却すれば削除できる
+        let checkouts = checkout_repo.find_unreturned_by_user_id(user_id).await?;
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkouts[0].id,
+                book_id,
+                user_id,
+                Utc::now(),
+            ))
+            .await?;
+        repo.delete(DeleteUser { user_id }).await?;
+        assert!(repo.find_by_id(user_id).await?.is_none());
+
+        let res = repo.delete(DeleteUser { user_id }).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_delete_owner_of_checked_out_book(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let checkout_repo = CheckoutRepositoryImpl::new(ConnectionPool::new(pool), 3, 14);
+        // 蔵書の所有者と、その蔵書を借りる別のユーザー（fixtures/checkout.sql参照）
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let borrower_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        checkout_repo
+            .create(CreateCheckout::new(book_id, borrower_id, Utc::now()))
+            .await?;
+
+        // 所有する蔵書が貸出中の間は削除できず、借りた側の貸出も残る
+        let res = repo.delete(DeleteUser { user_id: owner_id }).await;
+        assert!(matches!(res, Err(AppError::Conflict(_))));
+        assert!(repo.find_by_id(owner_id).await?.is_some());
+        let checkouts = checkout_repo
+            .find_unreturned_by_user_id(borrower_id)
+            .await?;
+        assert_eq!(checkouts.len(), 1);
+
+        // 返却されれば削除できる
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkouts[0].id,
+                book_id,
+                borrower_id,
+                Utc::now(),
+            ))
+            .await?;
+        repo.delete(DeleteUser { user_id: owner_id }).await?;
+        assert!(repo.find_by_id(owner_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_delete_invalidates_book_cache(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        use crate::redis::fake;
+        use crate::repository::book::BookRepositoryImpl;
+        use kernel::repository::book::BookRepository;
+
+        let kv = Arc::new(fake::start().await?);
+        let repo =
+            UserRepositoryImpl::new(ConnectionPool::new(pool.clone())).with_book_cache(kv.clone());
+        let book_repo = BookRepositoryImpl::new(ConnectionPool::new(pool)).with_cache(kv, 60);
+        // 蔵書の所有者（fixtures/checkout.sql参照）
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+
+        // 取得した蔵書がキャッシュに載った状態で所有者を削除する
+        assert!(book_repo.find_by_id(book_id).await?.is_some());
+        repo.delete(DeleteUser { user_id: owner_id }).await?;
+
+        // 連鎖して削除された蔵書は、キャッシュからも取得できない
+        assert!(book_repo.find_by_id(book_id).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_update_password(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user = repo
+            .create(CreateUser {
+                name: "Test User".into(),
+                email: "test.user@example.com".into(),
+                password: "test_password".into(),
+            })
+            .await?;
+        let update = |current: &str| UpdateUserPassword {
+            user_id: user.id,
+            current_password: current.into(),
+            new_password: "new_passw0rd".into(),
+        };
+
+        // 現在のパスワードが誤っていれば変更しない
+        let res = repo.update_password(update("wrong_password")).await;
+        assert!(matches!(res, Err(AppError::UnauthenticatedError)));
+
+        repo.update_password(update("test_password")).await?;
+        let stored: String =
+            sqlx::query_scalar("SELECT password_hash FROM users WHERE user_id = $1")
+                .bind(user.id.raw())
+                .fetch_one(&pool)
+                .await?;
+        assert!(verify_password("new_passw0rd", &stored).is_ok());
+        assert!(verify_password("test_password", &stored).is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "checkout"))]
+    async fn test_update_role(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = UserRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        assert_eq!(repo.find_by_id(user_id).await?.unwrap().role, Role::User);
+
+        // roles テーブルとの JOIN で、変更後のロールが返る
+        repo.update_role(UpdateUserRole {
+            user_id,
+            role: Role::Admin,
+        })
+        .await?;
+        assert_eq!(repo.find_by_id(user_id).await?.unwrap().role, Role::Admin);
+
+        let res = repo
+            .update_role(UpdateUserRole {
+                user_id: UserId::new(),
+                role: Role::Admin,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // roles テーブルにないロールには変更できない
+        sqlx::query!("UPDATE roles SET name = 'Member' WHERE name = 'User'")
+            .execute(&pool)
+            .await?;
+        let res = repo
+            .update_role(UpdateUserRole {
+                user_id,
+                role: Role::User,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::ValidationError(_))));
+        assert_eq!(repo.find_by_id(user_id).await?.unwrap().role, Role::Admin);
+
+        Ok(())
+    }
+}
@@ -10,6 +10,13 @@ use kernel::model::role::Role;
 use kernel::model::user::User;
 use registry::AppRegistry;
 use shared::error::AppError;
+
+// パスパラメータを取り出す。axum の Path と同じように使えるが、
+// 解釈に失敗した場合は他の API と同じ形式のエラーレスポンスを返す
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
+
 // リクエスト受信時のアクセストークンの検証処理
 pub struct AuthorizedUser {
     pub access_token: AccessToken,
@@ -24,6 +31,11 @@ impl AuthorizedUser {
     pub fn is_admin(&self) -> bool {
         self.user.role == Role::Admin
     }
+
+    // 指定したユーザーが所有するリソースを変更・削除してよいか。管理者または所有者本人のみ許可する
+    pub fn can_modify(&self, owner: UserId) -> bool {
+        self.is_admin() || self.id() == owner
+    }
 }
 
 #[async_trait]
@@ -60,3 +72,32 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
         Ok(Self { access_token, user })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authorized_user(role: Role) -> AuthorizedUser {
+        AuthorizedUser {
+            access_token: AccessToken("token".into()),
+            user: User {
+                id: UserId::new(),
+                name: "Test User".into(),
+                email: "test.user@example.com".into(),
+                role,
+            },
+        }
+    }
+
+    #[test]
+    fn test_can_modify() {
+        let admin = authorized_user(Role::Admin);
+        let user = authorized_user(Role::User);
+
+        // 管理者は他人のリソースも変更できる
+        assert!(admin.can_modify(user.id()));
+        // 一般ユーザーは自分のリソースだけ変更できる
+        assert!(user.can_modify(user.id()));
+        assert!(!user.can_modify(admin.id()));
+    }
+}
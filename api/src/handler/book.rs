@@ -1,22 +1,37 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{OriginalUri, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     Json,
 };
 use garde::Validate;
-use kernel::model::{book::event::DeleteBook, id::BookId};
+use kernel::model::{
+    book::{
+        event::{CreateBook, DeleteBook},
+        BookListOptions, BookSearchOptions,
+    },
+    id::BookId,
+};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::book::{
-        BookListQuery, BookResponse, CreateBookRequest, PaginatedBookResponse,
-        UpdaqteBookRequestWithIds, UpdateBookRequest,
+    extractor::{AuthorizedUser, Path},
+    model::{
+        book::{
+            BookCursorQuery, BookListQuery, BookResponse, BookSearchQuery, BulkCreateBookResult,
+            BulkCreateBooksQuery, BulkCreateBooksResponse, CreateBookRequest, CursorBookResponse,
+            PaginatedBookResponse, PatchBookRequest, PatchBookRequestWithIds,
+            UpdaqteBookRequestWithIds, UpdateBookRequest, MAX_BULK_CREATE_BOOKS,
+        },
+        list::PaginationHeaders,
     },
 };
 
+// 各ハンドラのスパンには、ログから対象を辿れるようユーザーや蔵書の ID だけを記録する。
+// リクエストボディや State を含めないよう、引数はすべて skip_all で除外する
 #[axum::debug_handler]
+#[tracing::instrument(skip_all, fields(user_id = %user.id()))]
 pub async fn register_book(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -31,38 +46,247 @@ pub async fn register_book(
         .map(|_| StatusCode::CREATED)
 }
 
+// 蔵書をまとめて登録する。行ごとの登録結果を返す
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), count = reqs.len()))]
+pub async fn register_books(
+    user: AuthorizedUser,
+    Query(query): Query<BulkCreateBooksQuery>,
+    State(registry): State<AppRegistry>,
+    Json(reqs): Json<Vec<CreateBookRequest>>,
+) -> AppResult<(StatusCode, Json<BulkCreateBooksResponse>)> {
+    if reqs.is_empty() || reqs.len() > MAX_BULK_CREATE_BOOKS {
+        return Err(AppError::UnprocessableEntity(format!(
+            "1度に登録できる蔵書は1件以上{}件以下です。",
+            MAX_BULK_CREATE_BOOKS
+        )));
+    }
+
+    let mut results = Vec::new();
+    let mut valid = Vec::new();
+    for (index, req) in reqs.into_iter().enumerate() {
+        match req.validate(&()) {
+            Ok(()) => valid.push((index, req.into())),
+            Err(report) => results.push(BulkCreateBookResult {
+                index,
+                book_id: None,
+                error: Some(report.to_string()),
+            }),
+        }
+    }
+
+    // partial でなければ、不正な行が1件でもあれば何も登録せずに結果を返す
+    if !query.partial && !results.is_empty() {
+        return Ok((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(BulkCreateBooksResponse {
+                created: 0,
+                results,
+            }),
+        ));
+    }
+
+    let (indexes, events): (Vec<_>, Vec<CreateBook>) = valid.into_iter().unzip();
+    // partial の場合は ISBN が重複する行も飛ばし、その行の結果にエラーとして返す
+    let book_ids: Vec<Result<BookId, String>> = if query.partial {
+        let isbns: Vec<String> = events.iter().map(|event| event.isbn.clone()).collect();
+        let book_ids = registry
+            .book_repository()
+            .create_many_partial(events, user.id())
+            .await?;
+        book_ids
+            .into_iter()
+            .zip(isbns)
+            .map(|(book_id, isbn)| {
+                book_id.ok_or_else(|| format!("ISBN（{isbn}）の蔵書は既に登録されています。"))
+            })
+            .collect()
+    } else {
+        registry
+            .book_repository()
+            .create_many(events, user.id())
+            .await?
+            .into_iter()
+            .map(Ok)
+            .collect()
+    };
+    let created = book_ids.iter().filter(|book_id| book_id.is_ok()).count();
+    results.extend(
+        indexes
+            .into_iter()
+            .zip(book_ids)
+            .map(|(index, book_id)| match book_id {
+                Ok(book_id) => BulkCreateBookResult {
+                    index,
+                    book_id: Some(book_id),
+                    error: None,
+                },
+                Err(error) => BulkCreateBookResult {
+                    index,
+                    book_id: None,
+                    error: Some(error),
+                },
+            }),
+    );
+    results.sort_by_key(|result| result.index);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BulkCreateBooksResponse { created, results }),
+    ))
+}
+
+// cursor を指定した場合はキーセット方式、指定しない場合は limit / offset で一覧する
 #[axum::debug_handler]
+#[tracing::instrument(skip_all, fields(user_id = %user.id()))]
 pub async fn show_book_list(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<BookListQuery>,
+    Query(cursor): Query<BookCursorQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Response> {
+    query.validate(&())?;
+    let cursor = cursor.cursor()?;
+    if cursor.is_some() {
+        query.validate_for_cursor()?;
+    }
+    let options = BookListOptions::from(query);
+    if let Some(cursor) = cursor {
+        let res = registry
+            .book_repository()
+            .find_all_after(cursor, options.limit)
+            .await
+            .map(|list| CursorBookResponse::from_list(list, user.is_admin()))?;
+
+        let favorite_book_ids = registry
+            .favorite_repository()
+            .find_favorite_book_ids(user.id(), &res.book_ids())
+            .await?;
+
+        return Ok(Json(res.mark_favorites(&favorite_book_ids)).into_response());
+    }
+
+    let res = registry
+        .book_repository()
+        .find_all(options)
+        .await
+        .map(|list| PaginatedBookResponse::from_list(list, user.is_admin()))?;
+
+    let favorite_book_ids = registry
+        .favorite_repository()
+        .find_favorite_book_ids(user.id(), &res.book_ids())
+        .await?;
+
+    let headers = PaginationHeaders::new(&uri, res.total, res.limit, res.offset, res.items.len());
+    Ok((headers, Json(res.mark_favorites(&favorite_book_ids))).into_response())
+}
+
+// ログイン中のユーザーが所有する蔵書だけを一覧する
+#[axum::debug_handler]
+#[tracing::instrument(skip_all, fields(user_id = %user.id()))]
+pub async fn show_my_book_list(
+    user: AuthorizedUser,
     Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<PaginatedBookResponse>> {
-    registry
+    query.validate(&())?;
+    let options = BookListOptions {
+        owned_by: Some(user.id()),
+        ..query.into()
+    };
+
+    let res = registry
+        .book_repository()
+        .find_all(options)
+        .await
+        .map(|list| PaginatedBookResponse::from_list(list, user.is_admin()))?;
+
+    let favorite_book_ids = registry
+        .favorite_repository()
+        .find_favorite_book_ids(user.id(), &res.book_ids())
+        .await?;
+
+    Ok(Json(res.mark_favorites(&favorite_book_ids)))
+}
+
+// 設定で検索対象とした項目からキーワードで蔵書を探す
+#[axum::debug_handler]
+#[tracing::instrument(skip_all, fields(user_id = %user.id()))]
+pub async fn search_books(
+    user: AuthorizedUser,
+    Query(search): Query<BookSearchQuery>,
+    Query(query): Query<BookListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedBookResponse>> {
+    let config = registry.config();
+    search.validate(&config.search)?;
+    query.validate(&())?;
+
+    let search = BookSearchOptions {
+        terms: search.terms(),
+        fields: config.search.fields.clone(),
+    };
+
+    let res = registry
         .book_repository()
-        .find_all(query.into())
+        .find_by_keyword(search, query.into())
         .await
-        .map(PaginatedBookResponse::from)
-        .map(Json)
+        .map(|list| PaginatedBookResponse::from_list(list, user.is_admin()))?;
+
+    let favorite_book_ids = registry
+        .favorite_repository()
+        .find_favorite_book_ids(user.id(), &res.book_ids())
+        .await?;
+
+    Ok(Json(res.mark_favorites(&favorite_book_ids)))
 }
 
 #[axum::debug_handler]
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), book_id = %book_id))]
 pub async fn show_book(
-    _user: AuthorizedUser,
+    user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<Json<BookResponse>> {
-    registry
+    let book = registry
         .book_repository()
         .find_by_id(book_id)
         .await
         .and_then(|bc| match bc {
-            Some(bc) => Ok(Json(bc.into())),
-            None => Err(AppError::EntityNotFound(
-                "The specific book was not found".to_string(),
-            )),
-        })
+            Some(bc) => Ok(BookResponse::from_book(bc, user.is_admin())),
+            None => Err(AppError::not_found("Book", book_id)),
+        })?;
+
+    let favorite_book_ids = registry
+        .favorite_repository()
+        .find_favorite_book_ids(user.id(), &[book.id])
+        .await?;
+
+    Ok(Json(book.mark_favorites(&favorite_book_ids)))
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), isbn = %isbn))]
+pub async fn show_book_by_isbn(
+    user: AuthorizedUser,
+    Path(isbn): Path<String>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<BookResponse>> {
+    let book = registry
+        .book_repository()
+        .find_by_isbn(&isbn)
+        .await?
+        .map(|book| BookResponse::from_book(book, user.is_admin()))
+        .ok_or_else(|| AppError::not_found("Book", &isbn))?;
+
+    let favorite_book_ids = registry
+        .favorite_repository()
+        .find_favorite_book_ids(user.id(), &[book.id])
+        .await?;
+
+    Ok(Json(book.mark_favorites(&favorite_book_ids)))
 }
 
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), book_id = %book_id))]
 pub async fn update_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
@@ -80,11 +304,39 @@ pub async fn update_book(
         .map(|_| StatusCode::OK)
 }
 
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), book_id = %book_id))]
+pub async fn patch_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PatchBookRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let patch_book = PatchBookRequestWithIds::new(book_id, user.id(), req);
+
+    registry
+        .book_repository()
+        .patch(patch_book.into())
+        .await
+        .map(|_| StatusCode::OK)
+}
+
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), book_id = %book_id))]
 pub async fn delete_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
+    let book = registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Book", book_id))?;
+    if !user.can_modify(book.owner.id) {
+        return Err(AppError::ForbiddenOperation);
+    }
+
     let delete_book = DeleteBook {
         book_id,
         requested_user: user.id(),
@@ -93,5 +345,80 @@ pub async fn delete_book(
         .book_repository()
         .delete(delete_book)
         .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+// 削除済みの蔵書を元に戻す。管理者のみ実行できる
+#[tracing::instrument(skip_all, fields(user_id = %user.id(), book_id = %book_id))]
+pub async fn restore_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .book_repository()
+        .restore(book_id)
+        .await
         .map(|_| StatusCode::OK)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use kernel::model::{id::UserId, role::Role};
+
+    use super::*;
+    use crate::test_util::{app_config, authorized_user, registry};
+
+    #[sqlx::test(
+        migrations = "../adapter/migrations",
+        fixtures(
+            "../../../adapter/src/repository/fixtures/common.sql",
+            "../../../adapter/src/repository/fixtures/book.sql"
+        )
+    )]
+    async fn test_update_book_without_description(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 説明のある蔵書と、その所有者（fixtures/book.sql参照）
+        let registry = registry(pool, app_config())?;
+        let owner_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let show = |registry: AppRegistry| async move {
+            let Json(book) = show_book(
+                authorized_user(owner_id, Role::User),
+                Path(book_id),
+                State(registry),
+            )
+            .await?;
+            anyhow::Ok(serde_json::to_value(book)?)
+        };
+        let book = show(registry.clone()).await?;
+        assert!(book["description"].is_string());
+
+        // description を省略して更新すると、空文字列ではなく null になる
+        let req: UpdateBookRequest = serde_json::from_value(serde_json::json!({
+            "title": book["title"],
+            "author": book["author"],
+            "isbn": book["isbn"],
+            "version": book["version"],
+        }))?;
+        let status = update_book(
+            authorized_user(owner_id, Role::User),
+            Path(book_id),
+            State(registry.clone()),
+            Json(req),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::OK);
+
+        let book = show(registry).await?;
+        assert!(book["description"].is_null());
+        assert_eq!(book["version"], 2);
+
+        Ok(())
+    }
+}
@@ -1,29 +1,69 @@
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use crate::{
+    extractor::{AuthorizedUser, Path},
+    model::{
+        book::BookListQuery,
+        checkout::{
+            CheckoutBatchRequest, CheckoutBatchResponse, CheckoutListQuery, CheckoutsResponse,
+            DailyCheckoutsQuery, DailyCheckoutsResponse, LostCheckoutsResponse,
+            OverdueCheckoutsResponse, PaginatedCheckoutResponse,
+        },
+    },
+};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Query, State},
+    http::{HeaderName, StatusCode},
+    response::IntoResponse,
     Json,
 };
+use garde::Validate;
 use kernel::model::{
-    book,
-    checkout::event::{CreateCheckout, UpdateReturned},
+    checkout::event::{CreateCheckout, CreateCheckouts, UpdateReturned},
     id::{BookId, CheckoutId},
 };
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::error::{AppError, AppResult};
+
+// 貸し出し後にあと何冊借りられるかを返すレスポンスヘッダ
+pub static X_CHECKOUT_REMAINING: HeaderName = HeaderName::from_static("x-checkout-remaining");
 
 pub async fn checkout_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
     State(registry): State<AppRegistry>,
-) -> AppResult<StatusCode> {
+) -> AppResult<impl IntoResponse> {
     let create_checkout_history = CreateCheckout::new(book_id, user.id(), chrono::Utc::now());
 
-    registry
+    let remaining = registry
         .checkout_repository()
         .create(create_checkout_history)
-        .await
-        .map(|_| StatusCode::CREATED)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(X_CHECKOUT_REMAINING.clone(), remaining.to_string())],
+    ))
+}
+
+pub async fn checkout_books(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CheckoutBatchRequest>,
+) -> AppResult<impl IntoResponse> {
+    req.validate(&())?;
+
+    let create_checkouts =
+        CreateCheckouts::new(req.book_ids, user.id(), chrono::Utc::now(), req.best_effort);
+
+    let batch = registry
+        .checkout_repository()
+        .create_batch(create_checkouts)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        [(X_CHECKOUT_REMAINING.clone(), batch.remaining.to_string())],
+        Json(CheckoutBatchResponse::from(batch.items)),
+    ))
 }
 
 pub async fn return_book(
@@ -36,8 +76,44 @@ pub async fn return_book(
     registry
         .checkout_repository()
         .update_returned(update_returned)
+        .await?;
+
+    // 順番待ちの先頭のユーザーに、返却されたことを知らせられるようにログに残す。
+    // 返却自体は完了しているため、順番待ちを取得できなくてもエラーにはしない
+    match registry.reservation_repository().find_queue(book_id).await {
+        Ok(queue) => {
+            if let Some(next) = queue.first() {
+                tracing::info!(
+                    book_id = %book_id,
+                    reservation_id = %next.reservation_id,
+                    user_id = %next.reserved_by.id,
+                    "Returned book is reserved, notify the next user"
+                );
+            }
+        }
+        Err(e) => {
+            tracing::warn!(error.message = %e, "Failed to look up the reservation queue");
+        }
+    }
+
+    Ok(StatusCode::OK)
+}
+
+// 誤って登録した貸出を取り消す。管理者だけが行える
+pub async fn cancel_checkout(
+    user: AuthorizedUser,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .checkout_repository()
+        .cancel(checkout_id, book_id)
         .await
-        .map(|_| StatusCode::OK)
+        .map(|_| StatusCode::NO_CONTENT)
 }
 
 pub async fn show_checked_out_list(
@@ -52,15 +128,144 @@ pub async fn show_checked_out_list(
         .map(Json)
 }
 
+// 管理者向けに、全蔵書の貸出を絞り込んで一覧する
+pub async fn show_checkout_list(
+    user: AuthorizedUser,
+    Query(query): Query<CheckoutListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedCheckoutResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+    query.validate(&())?;
+
+    let (filter, options) = query.into_filter_and_options();
+    registry
+        .checkout_repository()
+        .find_all(filter, options)
+        .await
+        .map(PaginatedCheckoutResponse::from)
+        .map(Json)
+}
+
 pub async fn checkout_history(
     _user: AuthorizedUser,
     Path(book_id): Path<BookId>,
+    Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
-) -> AppResult<Json<CheckoutsResponse>> {
+) -> AppResult<Json<PaginatedCheckoutResponse>> {
+    query.validate(&())?;
+
     registry
         .checkout_repository()
-        .find_history_by_book_id(book_id)
+        .find_history_by_book_id(book_id, query.into())
         .await
-        .map(CheckoutsResponse::from)
+        .map(PaginatedCheckoutResponse::from)
+        .map(Json)
+}
+
+pub async fn show_lost_checkouts(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<LostCheckoutsResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .checkout_repository()
+        .find_long_overdue(registry.config().checkout.lost_after_days)
+        .await
+        .map(LostCheckoutsResponse::from)
+        .map(Json)
+}
+
+pub async fn show_overdue_checkouts(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<OverdueCheckoutsResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .checkout_repository()
+        .find_overdue(chrono::Utc::now())
+        .await
+        .map(OverdueCheckoutsResponse::from)
+        .map(Json)
+}
+
+pub async fn show_daily_checkouts(
+    user: AuthorizedUser,
+    Query(query): Query<DailyCheckoutsQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<DailyCheckoutsResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+    query.validate(&())?;
+
+    registry
+        .checkout_repository()
+        .daily_counts(query.from, query.to)
+        .await
+        .map(DailyCheckoutsResponse::from)
         .map(Json)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use kernel::model::{id::UserId, role::Role};
+
+    use super::*;
+    use crate::test_util::{app_config, authorized_user, registry};
+
+    #[sqlx::test(
+        migrations = "../adapter/migrations",
+        fixtures(
+            "../../../adapter/src/repository/fixtures/common.sql",
+            "../../../adapter/src/repository/fixtures/checkout.sql"
+        )
+    )]
+    async fn test_checkout_remaining_header(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        // 上限3冊のユーザーと、貸し出す蔵書（fixtures/checkout.sql参照）
+        let registry = registry(pool, app_config())?;
+        let user_id = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let book_id1 = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let book_id2 = BookId::from_str("1ba2f6f3-0c7d-4b5f-a4d3-2c9b0c3e6a11")?;
+        let remaining = |res: axum::response::Response| {
+            res.headers()[&X_CHECKOUT_REMAINING]
+                .to_str()
+                .map(str::to_string)
+        };
+
+        // 貸し出すごとに、あと何冊借りられるかが減っていく
+        let res = checkout_book(
+            authorized_user(user_id, Role::User),
+            Path(book_id1),
+            State(registry.clone()),
+        )
+        .await?
+        .into_response();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(remaining(res)?, "2");
+
+        let res = checkout_books(
+            authorized_user(user_id, Role::User),
+            State(registry),
+            Json(CheckoutBatchRequest {
+                book_ids: vec![book_id2],
+                best_effort: false,
+            }),
+        )
+        .await?
+        .into_response();
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(remaining(res)?, "1");
+
+        Ok(())
+    }
+}
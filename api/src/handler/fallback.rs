@@ -0,0 +1,42 @@
+use axum::http::{Method, Uri};
+use shared::error::AppError;
+
+// どのルートにも一致しないリクエストにも、他の API と同じ形式のエラーボディで 404 を返す
+pub async fn route_not_found(method: Method, uri: Uri) -> AppError {
+    AppError::EntityNotFound(format!(
+        "{method} {} に対応する API は存在しません",
+        uri.path()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, extract::Request, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_route_not_found() -> anyhow::Result<()> {
+        let app = Router::new()
+            .nest("/api/v1", Router::new().route("/books", get(|| async {})))
+            .fallback(route_not_found);
+
+        for uri in ["/unknown", "/api/v1/unknown"] {
+            let res = app
+                .clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty())?)
+                .await?;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND);
+            let body = axum::body::to_bytes(res.into_body(), usize::MAX).await?;
+            let body: serde_json::Value = serde_json::from_slice(&body)?;
+            assert_eq!(body["error"]["code"], "entity_not_found");
+            assert_eq!(
+                body["error"]["message"],
+                format!("GET {uri} に対応する API は存在しません")
+            );
+        }
+
+        Ok(())
+    }
+}
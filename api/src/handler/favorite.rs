@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::model::{
+    favorite::event::{CreateFavorite, DeleteFavorite},
+    id::BookId,
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedUser, Path},
+    model::book::{BookListQuery, PaginatedBookResponse},
+};
+
+pub async fn add_favorite(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .favorite_repository()
+        .create(CreateFavorite::new(book_id, user.id()))
+        .await
+        .map(|_| StatusCode::CREATED)
+}
+
+pub async fn remove_favorite(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .favorite_repository()
+        .delete(DeleteFavorite::new(book_id, user.id()))
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+pub async fn show_favorite_list(
+    user: AuthorizedUser,
+    Query(query): Query<BookListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedBookResponse>> {
+    query.validate(&())?;
+
+    let res = registry
+        .book_repository()
+        .find_favorites(user.id(), query.into())
+        .await
+        .map(|list| PaginatedBookResponse::from_list(list, user.is_admin()))?;
+
+    // 一覧の蔵書はすべてお気に入りに登録済み
+    let favorite_book_ids = res.book_ids().into_iter().collect();
+    Ok(Json(res.mark_favorites(&favorite_book_ids)))
+}
@@ -1,14 +1,120 @@
-use axum::{extract::State, http::StatusCode};
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use kernel::repository::health::DependencyCheck;
 use registry::AppRegistry;
 
+use crate::model::health::{HealthInfoResponse, ReadinessResponse};
+
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+// バージョン・コミットハッシュ・実行環境を返す
+pub async fn health_info(State(registry): State<AppRegistry>) -> Json<HealthInfoResponse> {
+    Json(HealthInfoResponse::new(registry.config().environment))
+}
+
+// liveness（/health）とは別に、データベースに接続できるかを返す。接続できなければ 503
 pub async fn health_check_db(State(registry): State<AppRegistry>) -> StatusCode {
-    if registry.health_check_repository().check_db().await {
+    match registry.health_check_repository().check_db().await {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Database health check failed"
+            );
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+// すべての依存先が稼働していれば 200、ひとつでも稼働していなければ 503 を返す
+pub async fn readiness_check(
+    State(registry): State<AppRegistry>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    check_dependencies(&registry.dependency_checks()).await
+}
+
+async fn check_dependencies(
+    checks: &[Arc<dyn DependencyCheck>],
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        results.push((check.name(), check.is_ready().await));
+    }
+    let res: ReadinessResponse = results.into_iter().collect();
+
+    let status = if res.is_ready() {
         StatusCode::OK
     } else {
-        StatusCode::INTERNAL_SERVER_ERROR
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::async_trait;
+
+    use super::*;
+
+    struct FakeCheck(&'static str, bool);
+
+    #[async_trait]
+    impl DependencyCheck for FakeCheck {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn is_ready(&self) -> bool {
+            self.1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_info() -> anyhow::Result<()> {
+        let res = HealthInfoResponse::new(shared::env::Environment::Production);
+        let value = serde_json::to_value(res)?;
+
+        assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+        assert!(!value["commit"].as_str().unwrap_or_default().is_empty());
+        assert_eq!(value["environment"], "production");
+        // 返すのはこの3項目だけ
+        assert_eq!(value.as_object().map(|o| o.len()), Some(3));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_readiness_all_ok() -> anyhow::Result<()> {
+        let checks: Vec<Arc<dyn DependencyCheck>> = vec![Arc::new(FakeCheck("database", true))];
+        let (status, Json(res)) = check_dependencies(&checks).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            serde_json::to_value(res)?,
+            serde_json::json!({ "dependencies": { "database": "ok" }, "failed": [] })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_readiness_database_down() -> anyhow::Result<()> {
+        let checks: Vec<Arc<dyn DependencyCheck>> = vec![
+            Arc::new(FakeCheck("database", false)),
+            Arc::new(FakeCheck("cache", true)),
+        ];
+        let (status, Json(res)) = check_dependencies(&checks).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            serde_json::to_value(res)?,
+            serde_json::json!({
+                "dependencies": { "cache": "ok", "database": "unavailable" },
+                "failed": ["database"]
+            })
+        );
+        Ok(())
     }
 }
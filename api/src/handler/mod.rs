@@ -1,5 +1,10 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod fallback;
+pub mod favorite;
 pub mod health;
+pub mod reservation;
+pub mod stats;
+pub mod tag;
 pub mod user;
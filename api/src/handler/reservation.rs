@@ -0,0 +1,57 @@
+use axum::{extract::State, http::StatusCode, Json};
+use kernel::model::{
+    id::BookId,
+    reservation::event::{CreateReservation, DeleteReservation},
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{AuthorizedUser, Path},
+    model::reservation::{ReservationQueueResponse, ReservationResponse},
+};
+
+// 新たに予約した場合は 201、予約済みだった場合は 200 で既存の予約を返す
+pub async fn reserve_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<(StatusCode, Json<ReservationResponse>)> {
+    let reservation = registry
+        .reservation_repository()
+        .create(CreateReservation::new(book_id, user.id()))
+        .await?;
+
+    let status = if reservation.created {
+        StatusCode::CREATED
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(reservation.into())))
+}
+
+pub async fn show_reservation_queue(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationQueueResponse>> {
+    registry
+        .reservation_repository()
+        .find_queue(book_id)
+        .await
+        .map(ReservationQueueResponse::from)
+        .map(Json)
+}
+
+// 自分の予約を取り消す
+pub async fn cancel_reservation(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .reservation_repository()
+        .cancel(DeleteReservation::new(book_id, user.id()))
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
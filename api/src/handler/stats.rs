@@ -0,0 +1,21 @@
+use axum::{extract::State, Json};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{extractor::AuthorizedUser, model::stats::LibraryStatsResponse};
+
+pub async fn show_library_stats(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<LibraryStatsResponse>> {
+    if !user.is_admin() {
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    registry
+        .stats_repository()
+        .library_stats()
+        .await
+        .map(LibraryStatsResponse::from)
+        .map(Json)
+}
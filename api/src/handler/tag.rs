@@ -0,0 +1,71 @@
+use axum::{extract::State, http::StatusCode, Json};
+use kernel::model::{
+    id::BookId,
+    tag::event::{AttachTag, DetachTag},
+};
+use registry::AppRegistry;
+use shared::error::{AppError, AppResult};
+
+use crate::{
+    extractor::{AuthorizedUser, Path},
+    model::tag::{parse_tag_name, TagsResponse},
+};
+
+pub async fn show_tag_list(
+    _user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<TagsResponse>> {
+    registry
+        .tag_repository()
+        .list()
+        .await
+        .map(TagsResponse::from)
+        .map(Json)
+}
+
+// タグを付け外しできるのは、蔵書の所有者か管理者だけ
+pub async fn attach_tag(
+    user: AuthorizedUser,
+    Path((book_id, tag)): Path<(BookId, String)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let name = parse_tag_name(&tag)?;
+    authorize_tag_change(&user, book_id, &registry).await?;
+
+    registry
+        .tag_repository()
+        .attach(AttachTag::new(book_id, name))
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+pub async fn detach_tag(
+    user: AuthorizedUser,
+    Path((book_id, tag)): Path<(BookId, String)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let name = parse_tag_name(&tag)?;
+    authorize_tag_change(&user, book_id, &registry).await?;
+
+    registry
+        .tag_repository()
+        .detach(DetachTag::new(book_id, name))
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+async fn authorize_tag_change(
+    user: &AuthorizedUser,
+    book_id: BookId,
+    registry: &AppRegistry,
+) -> AppResult<()> {
+    let book = registry
+        .book_repository()
+        .find_by_id(book_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("Book", book_id))?;
+    if !user.can_modify(book.owner.id) {
+        return Err(AppError::ForbiddenOperation);
+    }
+    Ok(())
+}
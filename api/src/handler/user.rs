@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Query, State},
     http::StatusCode,
     Json,
 };
@@ -8,12 +8,13 @@ use kernel::model::{id::UserId, user::event::DeleteUser};
 use registry::AppRegistry;
 use shared::error::{AppError, AppResult};
 
-use crate::model::checkout::CheckoutsResponse;
+use crate::model::{book::BookListQuery, checkout::PaginatedCheckoutResponse};
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{AuthorizedUser, Path},
     model::user::{
         CreateUserRequest, UpdateUserPasswordRequest, UpdateUserPasswordRequestWithUserId,
-        UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserResponse, UsersResponse,
+        UpdateUserRoleRequest, UpdateUserRoleRequestWithUserId, UserDetailResponse, UserResponse,
+        UsersResponse,
     },
 };
 
@@ -21,7 +22,7 @@ pub async fn register_user(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateUserRequest>,
-) -> AppResult<Json<UserResponse>> {
+) -> AppResult<(StatusCode, Json<UserResponse>)> {
     if !user.is_admin() {
         return Err(AppError::ForbiddenOperation);
     }
@@ -29,7 +30,7 @@ pub async fn register_user(
     req.validate(&())?;
 
     let registered_user = registry.user_repository().create(req.into()).await?;
-    Ok(Json(registered_user.into()))
+    Ok((StatusCode::CREATED, Json(registered_user.into())))
 }
 
 pub async fn list_users(
@@ -47,6 +48,20 @@ pub async fn list_users(
     Ok(Json(UsersResponse { items }))
 }
 
+pub async fn show_user(
+    _user: AuthorizedUser,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<UserDetailResponse>> {
+    registry
+        .user_repository()
+        .find_detail_by_id(user_id)
+        .await?
+        .map(UserDetailResponse::from)
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("User", user_id))
+}
+
 pub async fn delete_user(
     user: AuthorizedUser,
     Path(user_id): Path<UserId>,
@@ -75,7 +90,7 @@ pub async fn change_role(
     }
     registry
         .user_repository()
-        .update_role(UpdateUserRoleRequestWithUserId::new(user_id, req).into())
+        .update_role(UpdateUserRoleRequestWithUserId::new(user_id, req).try_into()?)
         .await?;
 
     Ok(StatusCode::OK)
@@ -101,12 +116,31 @@ pub async fn change_password(
 
 pub async fn get_chekouts(
     user: AuthorizedUser,
+    Query(query): Query<BookListQuery>,
     State(registry): State<AppRegistry>,
-) -> AppResult<Json<CheckoutsResponse>> {
+) -> AppResult<Json<PaginatedCheckoutResponse>> {
+    query.validate(&())?;
+
+    registry
+        .checkout_repository()
+        .find_active_by_user(user.id(), query.into())
+        .await
+        .map(PaginatedCheckoutResponse::from)
+        .map(Json)
+}
+
+// 返却済みの貸出も含めた、ログイン中のユーザーの貸出履歴
+pub async fn get_checkout_history(
+    user: AuthorizedUser,
+    Query(query): Query<BookListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedCheckoutResponse>> {
+    query.validate(&())?;
+
     registry
         .checkout_repository()
-        .find_unreturned_by_user_id(user.id())
+        .find_history_by_user(user.id(), query.into())
         .await
-        .map(CheckoutsResponse::from)
+        .map(PaginatedCheckoutResponse::from)
         .map(Json)
 }
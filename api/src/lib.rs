@@ -1,4 +1,7 @@
 pub mod extractor;
 pub mod handler;
+pub mod middleware;
 pub mod model;
 pub mod route;
+#[cfg(test)]
+mod test_util;
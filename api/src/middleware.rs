@@ -0,0 +1,610 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{
+        header::{ALLOW, CONTENT_TYPE, RETRY_AFTER},
+        HeaderMap, HeaderValue, Response, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response as AxumResponse},
+};
+use axum_extra::headers::{authorization::Bearer, Authorization, HeaderMapExt};
+use kernel::{model::auth::AccessToken, repository::auth::AuthRepository};
+use shared::error::AppError;
+use tower_http::trace::{DefaultOnResponse, MakeSpan, OnResponse};
+use tracing::Span;
+
+// リクエストごとに採番し、レスポンスにも付与する相関 ID のヘッダー
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// リクエストのスパンに request_id を含め、ログからクライアントの報告を辿れるようにする。
+// SetRequestIdLayer で採番された ID を読むため、TraceLayer の外側に SetRequestIdLayer を置く。
+#[derive(Clone, Copy, Default)]
+pub struct RequestIdSpan;
+
+impl<B> MakeSpan<B> for RequestIdSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            uri = %request.uri(),
+            version = ?request.version(),
+            request_id,
+        )
+    }
+}
+
+// リクエストに付与されたルート定義のパスをレスポンス側にも引き継ぐ。
+// TraceLayer の on_response ではリクエストを参照できないため、このミドルウェアを TraceLayer の内側に置く。
+pub async fn propagate_matched_path(req: Request, next: Next) -> AxumResponse {
+    let matched_path = req.extensions().get::<MatchedPath>().cloned();
+    let mut res = next.run(req).await;
+    if let Some(matched_path) = matched_path {
+        res.extensions_mut().insert(matched_path);
+    }
+    res
+}
+
+// 記録するメトリクスの名前。レスポンス時間はヒストグラムのバケットを設定する際にも参照する
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+// リクエスト数とレスポンス時間を、メソッド・ルート・ステータスコードごとに記録する。
+// ID ごとに系列が増え続けないよう、実際のパスではなくルート定義のパスをラベルにする
+pub async fn record_metrics(req: Request, next: Next) -> AxumResponse {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let res = next.run(req).await;
+
+    let status = res.status().as_u16().to_string();
+    metrics::counter!(
+        HTTP_REQUESTS_TOTAL,
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status.clone(),
+    )
+    .increment(1);
+    metrics::histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        "method" => method,
+        "route" => route,
+        "status" => status,
+    )
+    .record(started.elapsed().as_secs_f64());
+    res
+}
+
+// 保持するクライアント数がこの件数を超えたら、満タンまで回復したバケットを捨てる
+const RATE_LIMIT_PRUNE_THRESHOLD: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: tokio::time::Instant,
+}
+
+// クライアントごとのトークンバケットでリクエスト数を制限する。
+// トークンは window あたり requests 個の速さで回復し、最大 requests 個まで貯まる
+#[derive(Clone)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<HashMap<String, Bucket>>>,
+    capacity: f64,
+    // 1 秒あたりに回復するトークン数
+    refill_rate: f64,
+    // クライアントを識別するため、アクセストークンの検証に使う
+    auth_repository: Arc<dyn AuthRepository>,
+}
+
+impl RateLimiter {
+    pub fn new(requests: u32, window: Duration, auth_repository: Arc<dyn AuthRepository>) -> Self {
+        let capacity = f64::from(requests);
+        Self {
+            buckets: Arc::default(),
+            capacity,
+            refill_rate: capacity / window.as_secs_f64(),
+            auth_repository,
+        }
+    }
+
+    // トークンを 1 つ消費する。足りない場合は次のトークンが貯まるまでの時間を返す
+    fn acquire(&self, key: &str) -> Result<(), Duration> {
+        let now = tokio::time::Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > RATE_LIMIT_PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| self.refilled(bucket, now) < self.capacity);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_rate,
+            ))
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: tokio::time::Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_rate).min(self.capacity)
+    }
+
+    // 検証できたアクセストークンを持つリクエストはユーザーごとに数える。
+    // 未認証のリクエストは接続元 IP ごとに数える。Authorization ヘッダーの値をそのまま
+    // キーにすると、リクエストごとに変えるだけで別のバケットになってしまう
+    async fn key(&self, headers: &HeaderMap, addr: Option<&ConnectInfo<SocketAddr>>) -> String {
+        if let Some(Authorization(bearer)) = headers.typed_get::<Authorization<Bearer>>() {
+            let access_token = AccessToken(bearer.token().to_string());
+            match self
+                .auth_repository
+                .fetch_user_id_from_token(&access_token)
+                .await
+            {
+                Ok(Some(user_id)) => return format!("user:{user_id}"),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(error.message = %e, "Failed to verify access token"),
+            }
+        }
+        match addr {
+            Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+            None => "unknown".to_string(),
+        }
+    }
+}
+
+// 上限を超えたリクエストには 429 を返し、Retry-After で再試行できるまでの秒数を伝える
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> AxumResponse {
+    // Request は Sync ではないため、参照を保持したまま await しないよう必要な値だけ渡す
+    let key = limiter
+        .key(
+            req.headers(),
+            req.extensions().get::<ConnectInfo<SocketAddr>>(),
+        )
+        .await;
+    match limiter.acquire(&key) {
+        Ok(()) => next.run(req).await,
+        Err(wait) => {
+            let mut res = AppError::TooManyRequests.into_response();
+            let secs = wait.as_secs_f64().ceil().max(1.0) as u64;
+            res.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs));
+            res
+        }
+    }
+}
+
+// ルーターが返す 405 はボディが空のため、Allow ヘッダーを残したまま AppError の形式のボディに置き換える
+pub async fn method_not_allowed(req: Request, next: Next) -> AxumResponse {
+    let method = req.method().clone();
+    let res = next.run(req).await;
+    // ハンドラが返した AppError などボディのあるレスポンスはそのまま返す
+    if res.status() != StatusCode::METHOD_NOT_ALLOWED || res.headers().contains_key(CONTENT_TYPE) {
+        return res;
+    }
+
+    let mut error = AppError::MethodNotAllowed(format!("メソッド {method} には対応していません"))
+        .into_response();
+    if let Some(allow) = res.headers().get(ALLOW) {
+        error.headers_mut().insert(ALLOW, allow.clone());
+    }
+    error
+}
+
+// 制限時間内に終わらないリクエストの処理を打ち切り、504 を返す。
+// 打ち切った時点で処理中の Future は破棄され、保持していたデータベースの接続なども解放される
+pub async fn request_timeout(
+    State(duration): State<Duration>,
+    req: Request,
+    next: Next,
+) -> AxumResponse {
+    let method = req.method().clone();
+    let uri = req.uri().clone();
+    match tokio::time::timeout(duration, next.run(req)).await {
+        Ok(res) => res,
+        Err(_) => {
+            tracing::warn!(%method, %uri, timeout_ms = duration.as_millis() as u64, "request timed out");
+            AppError::RequestTimeout.into_response()
+        }
+    }
+}
+
+// TraceLayer が計測したレスポンス時間を、ルートごとに設定した目安と比較する。
+// 目安を超えた場合は WARN ログを出し、通常のレスポンスログは inner に任せる。
+#[derive(Clone)]
+pub struct LatencyBudget {
+    budgets: Arc<HashMap<String, Duration>>,
+    inner: DefaultOnResponse,
+}
+
+impl LatencyBudget {
+    pub fn new(budgets: &HashMap<String, u64>) -> Self {
+        let budgets = budgets
+            .iter()
+            .map(|(route, millis)| (route.clone(), Duration::from_millis(*millis)))
+            .collect();
+        Self {
+            budgets: Arc::new(budgets),
+            inner: DefaultOnResponse::default(),
+        }
+    }
+
+    pub fn with_inner(mut self, inner: DefaultOnResponse) -> Self {
+        self.inner = inner;
+        self
+    }
+}
+
+impl<B> OnResponse<B> for LatencyBudget {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        if let Some(route) = response.extensions().get::<MatchedPath>() {
+            if let Some(budget) = self.budgets.get(route.as_str()) {
+                if latency > *budget {
+                    tracing::warn!(
+                        route = route.as_str(),
+                        latency_ms = latency.as_millis() as u64,
+                        budget_ms = budget.as_millis() as u64,
+                        "response exceeded latency budget"
+                    );
+                }
+            }
+        }
+        self.inner.on_response(response, latency, span);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use axum::{
+        body::Body,
+        http::{header::AUTHORIZATION, HeaderName},
+        middleware,
+        routing::{get, post},
+        Router,
+    };
+    use tower::ServiceExt;
+    use tower_http::{
+        request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+        trace::TraceLayer,
+    };
+
+    use kernel::model::{auth::event::CreateToken, id::UserId};
+    use shared::error::AppResult;
+
+    use super::*;
+
+    // 登録したアクセストークンだけを有効なものとして扱う。それ以外の操作は認証の失敗として返す
+    struct FakeAuthRepository(HashMap<String, UserId>);
+
+    #[axum::async_trait]
+    impl AuthRepository for FakeAuthRepository {
+        async fn fetch_user_id_from_token(
+            &self,
+            access_token: &AccessToken,
+        ) -> AppResult<Option<UserId>> {
+            Ok(self.0.get(&access_token.0).copied())
+        }
+        async fn verify_user(&self, _email: &str, _password: &str) -> AppResult<UserId> {
+            Err(AppError::UnauthenticatedError)
+        }
+        async fn create_token(&self, _event: CreateToken) -> AppResult<AccessToken> {
+            Err(AppError::UnauthenticatedError)
+        }
+        async fn delete_token(&self, _access_token: &AccessToken) -> AppResult<()> {
+            Err(AppError::UnauthenticatedError)
+        }
+    }
+
+    // 10 秒あたり 2 回まで
+    fn limiter(tokens: &[(&str, UserId)]) -> RateLimiter {
+        let auth = FakeAuthRepository(
+            tokens
+                .iter()
+                .map(|(token, user_id)| (token.to_string(), *user_id))
+                .collect(),
+        );
+        RateLimiter::new(2, Duration::from_secs(10), Arc::new(auth))
+    }
+
+    #[derive(Clone, Default)]
+    struct CapturedLog(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLog {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn warn_when_latency_budget_exceeded() -> anyhow::Result<()> {
+        let log = CapturedLog::default();
+        let writer = log.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::WARN)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let budgets = HashMap::from([("/slow".to_string(), 10), ("/fast".to_string(), 10_000)]);
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async { tokio::time::sleep(Duration::from_millis(50)).await }),
+            )
+            .route("/fast", get(|| async {}))
+            .layer(middleware::from_fn(propagate_matched_path))
+            .layer(TraceLayer::new_for_http().on_response(LatencyBudget::new(&budgets)));
+
+        for uri in ["/fast", "/slow"] {
+            app.clone()
+                .oneshot(Request::builder().uri(uri).body(Body::empty())?)
+                .await?;
+        }
+
+        let output = String::from_utf8(log.0.lock().unwrap().clone())?;
+        let warnings: Vec<&str> = output
+            .lines()
+            .filter(|line| line.contains("exceeded latency budget"))
+            .collect();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("route=\"/slow\""));
+        assert!(warnings[0].contains("budget_ms=10"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn propagate_request_id() -> anyhow::Result<()> {
+        let log = CapturedLog::default();
+        let writer = log.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async {
+                    tracing::info!("handled");
+                }),
+            )
+            .layer(TraceLayer::new_for_http().make_span_with(RequestIdSpan))
+            .layer(PropagateRequestIdLayer::new(HeaderName::from_static(
+                REQUEST_ID_HEADER,
+            )))
+            .layer(SetRequestIdLayer::new(
+                HeaderName::from_static(REQUEST_ID_HEADER),
+                MakeRequestUuid,
+            ));
+
+        // ヘッダーがなければ UUID を採番してレスポンスに返す
+        let res = app
+            .clone()
+            .oneshot(Request::builder().uri("/").body(Body::empty())?)
+            .await?;
+        let request_id = res.headers()[REQUEST_ID_HEADER].to_str()?.to_string();
+        assert!(uuid::Uuid::parse_str(&request_id).is_ok());
+
+        // クライアントが指定した ID はそのまま引き継ぐ
+        let res = app
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header(REQUEST_ID_HEADER, "client-supplied-id")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(res.headers()[REQUEST_ID_HEADER], "client-supplied-id");
+
+        let output = String::from_utf8(log.0.lock().unwrap().clone())?;
+        let handled: Vec<&str> = output
+            .lines()
+            .filter(|line| line.contains("handled"))
+            .collect();
+        assert_eq!(handled.len(), 2);
+        assert!(handled[0].contains(&format!("request_id=\"{request_id}\"")));
+        assert!(handled[1].contains("request_id=\"client-supplied-id\""));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_slow_request() -> anyhow::Result<()> {
+        let app = Router::new()
+            .route(
+                "/slow",
+                get(|| async { tokio::time::sleep(Duration::from_secs(10)).await }),
+            )
+            .route("/fast", get(|| async {}))
+            .layer(middleware::from_fn_with_state(
+                Duration::from_secs(1),
+                request_timeout,
+            ));
+
+        let res = app
+            .clone()
+            .oneshot(Request::builder().uri("/slow").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), axum::http::StatusCode::GATEWAY_TIMEOUT);
+        let res = app
+            .oneshot(Request::builder().uri("/fast").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn method_not_allowed_with_allow_header() -> anyhow::Result<()> {
+        let app = Router::new()
+            .route("/books", get(|| async {}).post(|| async {}))
+            .layer(middleware::from_fn(method_not_allowed));
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/books")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let allow = res.headers().get(ALLOW).unwrap().to_str()?.to_string();
+        let mut methods: Vec<&str> = allow.split(',').map(str::trim).collect();
+        methods.sort();
+        assert_eq!(methods, vec!["GET", "HEAD", "POST"]);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await?;
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(body["error"]["code"], "method_not_allowed");
+
+        let res = app
+            .oneshot(Request::builder().uri("/books").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_ignores_rotating_tokens() -> anyhow::Result<()> {
+        let app = Router::new()
+            .route("/auth/login", post(|| async {}))
+            .layer(middleware::from_fn_with_state(limiter(&[]), rate_limit));
+        let request = |i: usize| {
+            let mut req = Request::builder()
+                .method("POST")
+                .uri("/auth/login")
+                .header(AUTHORIZATION, format!("Bearer junk-{i}"))
+                .body(Body::empty())?;
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([192, 0, 2, 1], 12345))));
+            anyhow::Ok(req)
+        };
+
+        // リクエストごとに Authorization を変えても、同じ接続元からは上限を超えられない
+        for i in 0..2 {
+            let res = app.clone().oneshot(request(i)?).await?;
+            assert_eq!(res.status(), axum::http::StatusCode::OK);
+        }
+        let res = app.oneshot(request(2)?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_per_client() -> anyhow::Result<()> {
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(middleware::from_fn_with_state(limiter(&[]), rate_limit));
+        let request = |ip: [u8; 4], token: &str| {
+            let mut req = Request::builder()
+                .uri("/")
+                .header(AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())?;
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 12345))));
+            anyhow::Ok(req)
+        };
+        let a = [192, 0, 2, 1];
+        let b = [192, 0, 2, 2];
+
+        for _ in 0..2 {
+            let res = app.clone().oneshot(request(a, "a")?).await?;
+            assert_eq!(res.status(), axum::http::StatusCode::OK);
+        }
+
+        // 3 回目は拒否され、トークンが 1 つ回復するまでの 5 秒を待つよう伝える
+        let res = app.clone().oneshot(request(a, "a")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers()[RETRY_AFTER], "5");
+
+        // 別のクライアントは影響を受けない
+        let res = app.clone().oneshot(request(b, "a")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+
+        // 時間が経てば再びリクエストできる
+        tokio::time::advance(Duration::from_secs(5)).await;
+        let res = app.clone().oneshot(request(a, "a")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+        let res = app.oneshot(request(a, "a")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit_per_verified_user() -> anyhow::Result<()> {
+        let alice = UserId::new();
+        let bob = UserId::new();
+        let app = Router::new()
+            .route("/", get(|| async {}))
+            .layer(middleware::from_fn_with_state(
+                limiter(&[("alice", alice), ("bob", bob)]),
+                rate_limit,
+            ));
+        let request = |ip: [u8; 4], token: &str| {
+            let mut req = Request::builder()
+                .uri("/")
+                .header(AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())?;
+            req.extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 12345))));
+            anyhow::Ok(req)
+        };
+        let a = [192, 0, 2, 1];
+        let b = [192, 0, 2, 2];
+
+        // 検証できたユーザーは、接続元が変わっても同じ上限を共有する
+        let res = app.clone().oneshot(request(a, "alice")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+        let res = app.clone().oneshot(request(b, "alice")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+        let res = app.clone().oneshot(request(a, "alice")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+
+        // 同じ接続元でも、別のユーザーや未認証のリクエストは影響を受けない
+        let res = app.clone().oneshot(request(a, "bob")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+        let res = app.oneshot(request(a, "unknown")?).await?;
+        assert_eq!(res.status(), axum::http::StatusCode::OK);
+
+        Ok(())
+    }
+}
@@ -1,29 +1,51 @@
-use super::user::{BookOwner, CheckoutUser};
-use axum_extra::headers::Date;
+use std::{collections::HashSet, str::FromStr};
+
+use super::{
+    tag::{normalize_tag_name, validate_tag_name},
+    user::{BookOwner, CheckoutUser},
+};
 use chrono::{DateTime, Utc};
 use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     book::{
-        event::{CreateBook, UpdateBook},
-        Book, BookListOptions, Checkout,
+        event::{CreateBook, PatchBook, UpdateBook},
+        Book, BookListOptions, BookSortKey, Checkout, SortOrder, TagMatch,
     },
     id::{BookId, CheckoutId, UserId},
-    list::PaginatedList,
+    list::{CursorList, PaginatedList},
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use shared::{
+    config::SearchConfig,
+    error::{AppError, AppResult},
+};
+
+// books テーブルの列の長さ（文字数）に合わせた上限
+const MAX_TITLE_LENGTH: usize = 255;
+const MAX_AUTHOR_LENGTH: usize = 255;
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
 
 #[derive(Debug, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBookRequest {
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_not_blank), length(chars, max = MAX_TITLE_LENGTH))]
     pub title: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_not_blank), length(chars, max = MAX_AUTHOR_LENGTH))]
     pub author: String,
     #[garde(length(min = 1))]
     pub isbn: String,
-    #[garde(skip)]
-    pub description: String,
+    // 省略した場合や空文字列の場合は、説明のない蔵書として登録する
+    #[garde(length(chars, max = MAX_DESCRIPTION_LENGTH))]
+    pub description: Option<String>,
+}
+
+// 空白だけの文字列も空として扱う
+fn validate_not_blank(value: &str, _: &()) -> garde::Result {
+    if value.trim().is_empty() {
+        return Err(garde::Error::new("must not be blank"));
+    }
+    Ok(())
 }
 
 impl From<CreateBookRequest> for CreateBook {
@@ -38,22 +60,55 @@ impl From<CreateBookRequest> for CreateBook {
             title,
             author,
             isbn,
-            description,
+            description: description.filter(|d| !d.is_empty()),
         }
     }
 }
 
+// 一括登録で一度に受け付ける蔵書の最大件数
+pub const MAX_BULK_CREATE_BOOKS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct BulkCreateBooksQuery {
+    // true の場合は不正な行や ISBN が重複する行を除いて登録する。
+    // false の場合は1件でも登録できない行があれば何も登録しない
+    #[serde(default)]
+    pub partial: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkCreateBooksResponse {
+    pub created: usize,
+    pub results: Vec<BulkCreateBookResult>,
+}
+
+// リクエストの配列の index 番目の蔵書の登録結果。登録できた場合は bookId、できなかった場合は error を返す
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkCreateBookResult {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book_id: Option<BookId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBookRequest {
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_not_blank), length(chars, max = MAX_TITLE_LENGTH))]
     pub title: String,
-    #[garde(length(min = 1))]
+    #[garde(custom(validate_not_blank), length(chars, max = MAX_AUTHOR_LENGTH))]
     pub author: String,
     #[garde(length(min = 1))]
     pub isbn: String,
+    // 省略した場合や空文字列の場合は、説明のない蔵書として保存する
+    #[garde(length(chars, max = MAX_DESCRIPTION_LENGTH))]
+    pub description: Option<String>,
+    // 蔵書を取得したときの version。他のユーザーが先に更新していた場合は 409 を返す
     #[garde(skip)]
-    pub description: String,
+    pub version: i32,
 }
 #[derive(new)]
 pub struct UpdaqteBookRequestWithIds(BookId, UserId, UpdateBookRequest);
@@ -68,9 +123,65 @@ impl From<UpdaqteBookRequestWithIds> for UpdateBook {
                 author,
                 isbn,
                 description,
+                version,
             },
         ) = value;
         UpdateBook {
+            book_id,
+            title,
+            author,
+            isbn,
+            description: description.filter(|d| !d.is_empty()),
+            requested_user: user_id,
+            version,
+        }
+    }
+}
+
+// PATCH 用のリクエスト。各項目の扱いは以下の通り
+// - 項目なし: 変更しない
+// - null: NULL 許容の項目（description）は値を消去する
+// - 値あり: その値で更新する
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchBookRequest {
+    #[garde(inner(custom(validate_not_blank), length(chars, max = MAX_TITLE_LENGTH)))]
+    pub title: Option<String>,
+    #[garde(inner(custom(validate_not_blank), length(chars, max = MAX_AUTHOR_LENGTH)))]
+    pub author: Option<String>,
+    #[garde(length(min = 1))]
+    pub isbn: Option<String>,
+    #[garde(inner(inner(length(chars, max = MAX_DESCRIPTION_LENGTH))))]
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub description: Option<Option<String>>,
+}
+
+// 項目が存在すれば null であっても Some として扱う。
+// #[serde(default)] と組み合わせることで、項目なしの場合だけ None になる
+fn deserialize_present<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(new)]
+pub struct PatchBookRequestWithIds(BookId, UserId, PatchBookRequest);
+
+impl From<PatchBookRequestWithIds> for PatchBook {
+    fn from(value: PatchBookRequestWithIds) -> Self {
+        let PatchBookRequestWithIds(
+            book_id,
+            user_id,
+            PatchBookRequest {
+                title,
+                author,
+                isbn,
+                description,
+            },
+        ) = value;
+        PatchBook {
             book_id,
             title,
             author,
@@ -88,13 +199,27 @@ pub struct BookResponse {
     pub title: String,
     pub author: String,
     pub isbn: String,
-    pub description: String,
+    pub description: Option<String>,
     pub owner: BookOwner,
     pub checkout: Option<BookCheckoutResponse>,
+    pub tags: Vec<String>,
+    // 貸出中でなければ借りられる。クライアントが checkout から判定しなくて済むよう返す
+    pub is_available: bool,
+    pub is_favorite: bool,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
 
 impl From<Book> for BookResponse {
     fn from(value: Book) -> Self {
+        Self::from_book(value, false)
+    }
+}
+
+impl BookResponse {
+    // 所有者のメールアドレスは連絡用に管理者にだけ公開する
+    pub fn from_book(value: Book, include_owner_email: bool) -> Self {
         let Book {
             id,
             title,
@@ -103,6 +228,10 @@ impl From<Book> for BookResponse {
             description,
             owner,
             checkout,
+            tags,
+            version,
+            created_at,
+            updated_at,
         } = value;
         Self {
             id,
@@ -110,10 +239,27 @@ impl From<Book> for BookResponse {
             author,
             isbn,
             description,
-            owner: owner.into(),
+            owner: if include_owner_email {
+                BookOwner::with_email(owner)
+            } else {
+                owner.into()
+            },
+            is_available: checkout.is_none(),
             checkout: checkout.map(BookCheckoutResponse::from),
+            tags,
+            // お気に入りかどうかはリクエストしたユーザーごとに異なるため、
+            // ハンドラで mark_favorites を呼んで設定する
+            is_favorite: false,
+            version,
+            created_at,
+            updated_at,
         }
     }
+
+    pub fn mark_favorites(mut self, favorite_book_ids: &HashSet<BookId>) -> Self {
+        self.is_favorite = favorite_book_ids.contains(&self.id);
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -124,24 +270,295 @@ pub struct BookListQuery {
     #[garde(range(min = 0))]
     #[serde(default)]
     pub offset: i64,
+    #[garde(skip)]
+    #[serde(default)]
+    pub sort: BookSortQuery,
+    // 未指定の場合は並び替えの項目ごとの既定の向きになる
+    #[garde(skip)]
+    pub order: Option<SortOrderQuery>,
+    // true の場合は貸出中でない蔵書だけを返す
+    #[garde(skip)]
+    #[serde(default)]
+    pub available: bool,
+    // 指定した場合はそのユーザーが所有する蔵書だけを返す
+    #[garde(skip)]
+    pub owned_by: Option<UserId>,
+    // 指定した場合は登録日時がこの範囲（両端を含む）の蔵書だけを返す
+    #[garde(skip)]
+    pub created_after: Option<DateTime<Utc>>,
+    #[garde(custom(validate_created_range(&self.created_after)))]
+    pub created_before: Option<DateTime<Utc>>,
+    // 指定した場合はそのタグが付いた蔵書だけを返す。大文字・小文字は区別しない
+    #[garde(inner(custom(validate_tag_name)))]
+    pub tag: Option<String>,
+    // カンマ区切りで複数のタグを指定する。tag を指定した場合はそれも含める
+    #[garde(length(max = MAX_FILTER_TAGS), inner(custom(validate_tag_name)))]
+    #[serde(default, deserialize_with = "deserialize_comma_separated")]
+    pub tags: Vec<String>,
+    // 複数のタグをすべて含む蔵書（all）といずれかを含む蔵書（any）のどちらを返すか
+    #[garde(skip)]
+    #[serde(default, rename = "match")]
+    pub tag_match: TagMatchQuery,
+    // false の場合は総件数を数えず、レスポンスの total を null にする
+    #[garde(skip)]
+    #[serde(default = "default_with_total")]
+    pub with_total: bool,
+}
+
+const fn default_with_total() -> bool {
+    true
+}
+
+impl BookListQuery {
+    // cursor で一覧する場合は登録日時の新しい順に並べるだけで、絞り込みや並び替え、offset には対応しない。
+    // 指定された条件を黙って無視しないよう、併用された項目をエラーとして返す
+    pub fn validate_for_cursor(&self) -> AppResult<()> {
+        let unsupported = [
+            ("offset", self.offset != 0),
+            ("sort", !matches!(self.sort, BookSortQuery::CreatedAt)),
+            ("order", matches!(self.order, Some(SortOrderQuery::Asc))),
+            ("available", self.available),
+            ("owned_by", self.owned_by.is_some()),
+            ("created_after", self.created_after.is_some()),
+            ("created_before", self.created_before.is_some()),
+            ("tag", self.tag.is_some()),
+            ("tags", !self.tags.is_empty()),
+        ];
+        match unsupported.into_iter().find(|(_, set)| *set) {
+            Some((field, _)) => Err(AppError::invalid_field(
+                field,
+                "cannot be combined with cursor",
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+// 一度に絞り込みに使えるタグの最大数
+const MAX_FILTER_TAGS: usize = 10;
+
+// "a,b,c" を ["a", "b", "c"] にする。空の要素は捨てる
+fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    Ok(value
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn validate_created_range(
+    created_after: &Option<DateTime<Utc>>,
+) -> impl FnOnce(&Option<DateTime<Utc>>, &()) -> garde::Result + '_ {
+    move |created_before, _| match (created_after, created_before) {
+        (Some(after), Some(before)) if after > before => {
+            Err(garde::Error::new("must not be before `created_after`"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BookSortQuery {
+    #[default]
+    CreatedAt,
+    Title,
+    Author,
+}
+
+impl From<BookSortQuery> for BookSortKey {
+    fn from(value: BookSortQuery) -> Self {
+        match value {
+            BookSortQuery::CreatedAt => Self::CreatedAt,
+            BookSortQuery::Title => Self::Title,
+            BookSortQuery::Author => Self::Author,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagMatchQuery {
+    #[default]
+    All,
+    Any,
+}
+
+impl From<TagMatchQuery> for TagMatch {
+    fn from(value: TagMatchQuery) -> Self {
+        match value {
+            TagMatchQuery::All => Self::All,
+            TagMatchQuery::Any => Self::Any,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrderQuery {
+    Asc,
+    Desc,
+}
+
+impl From<SortOrderQuery> for SortOrder {
+    fn from(value: SortOrderQuery) -> Self {
+        match value {
+            SortOrderQuery::Asc => Self::Asc,
+            SortOrderQuery::Desc => Self::Desc,
+        }
+    }
 }
 
 const DEFAULT_LIMIT: i64 = 20;
-const fn default_limit() -> i64 {
+// 1 ページで返す件数の上限。これより大きい limit は丸める
+pub(crate) const MAX_LIMIT: i64 = 100;
+pub(crate) const fn default_limit() -> i64 {
     DEFAULT_LIMIT
 }
 
 impl From<BookListQuery> for BookListOptions {
     fn from(value: BookListQuery) -> Self {
-        let BookListQuery { limit, offset } = value;
-        Self { limit, offset }
+        let BookListQuery {
+            limit,
+            offset,
+            sort,
+            order,
+            available,
+            owned_by,
+            created_after,
+            created_before,
+            tag,
+            tags,
+            tag_match,
+            with_total,
+        } = value;
+        let sort = BookSortKey::from(sort);
+        Self {
+            limit: limit.min(MAX_LIMIT),
+            offset,
+            sort,
+            order: order.map_or_else(|| sort.default_order(), SortOrder::from),
+            owned_by,
+            only_available: available,
+            created_after,
+            created_before,
+            tags: tag
+                .iter()
+                .chain(tags.iter())
+                .map(|t| normalize_tag_name(t))
+                .collect(),
+            tag_match: tag_match.into(),
+            skip_total: !with_total,
+        }
+    }
+}
+
+// 蔵書のキーワード検索用のクエリ
+#[derive(Debug, Deserialize, Validate)]
+#[garde(context(SearchConfig))]
+pub struct BookSearchQuery {
+    #[garde(custom(validate_search_keyword))]
+    #[serde(default)]
+    pub q: String,
+}
+
+impl BookSearchQuery {
+    // tsquery の演算子（& | ! ( ) : * など）として解釈される記号や空白で区切り、
+    // 英数字だけの単語に分割して返す。空の単語は捨てる
+    pub fn terms(&self) -> Vec<String> {
+        split_search_terms(&self.q)
+    }
+}
+
+fn split_search_terms(q: &str) -> Vec<String> {
+    q.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn validate_search_keyword(value: &str, config: &SearchConfig) -> garde::Result {
+    if value.chars().count() > config.max_query_length {
+        return Err(garde::Error::new(format!(
+            "must be at most {} characters",
+            config.max_query_length
+        )));
+    }
+    if split_search_terms(value).len() > config.max_terms {
+        return Err(garde::Error::new(format!(
+            "must contain at most {} terms",
+            config.max_terms
+        )));
+    }
+    Ok(())
+}
+
+// cursor を指定した場合はキーセット方式で一覧する。空文字の場合は先頭から取得する
+#[derive(Debug, Deserialize)]
+pub struct BookCursorQuery {
+    pub cursor: Option<String>,
+}
+
+impl BookCursorQuery {
+    pub fn cursor(&self) -> AppResult<Option<Option<BookId>>> {
+        self.cursor
+            .as_deref()
+            .map(|cursor| match cursor {
+                "" => Ok(None),
+                cursor => BookId::from_str(cursor).map(Some),
+            })
+            .transpose()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorBookResponse {
+    pub limit: i64,
+    pub next_cursor: Option<BookId>,
+    pub items: Vec<BookResponse>,
+}
+
+impl CursorBookResponse {
+    pub fn from_list(value: CursorList<Book, BookId>, include_owner_email: bool) -> Self {
+        let CursorList {
+            limit,
+            items,
+            next_cursor,
+        } = value;
+        Self {
+            limit,
+            next_cursor,
+            items: items
+                .into_iter()
+                .map(|book| BookResponse::from_book(book, include_owner_email))
+                .collect(),
+        }
+    }
+
+    pub fn book_ids(&self) -> Vec<BookId> {
+        self.items.iter().map(|book| book.id).collect()
+    }
+
+    pub fn mark_favorites(mut self, favorite_book_ids: &HashSet<BookId>) -> Self {
+        self.items = self
+            .items
+            .into_iter()
+            .map(|book| book.mark_favorites(favorite_book_ids))
+            .collect();
+        self
     }
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedBookResponse {
-    pub total: i64,
+    // with_total=false で総件数を数えなかった場合は null
+    pub total: Option<i64>,
     pub limit: i64,
     pub offset: i64,
     pub items: Vec<BookResponse>,
@@ -149,6 +566,12 @@ pub struct PaginatedBookResponse {
 
 impl From<PaginatedList<Book>> for PaginatedBookResponse {
     fn from(value: PaginatedList<Book>) -> Self {
+        Self::from_list(value, false)
+    }
+}
+
+impl PaginatedBookResponse {
+    pub fn from_list(value: PaginatedList<Book>, include_owner_email: bool) -> Self {
         let PaginatedList {
             total,
             limit,
@@ -159,9 +582,25 @@ impl From<PaginatedList<Book>> for PaginatedBookResponse {
             total,
             limit,
             offset,
-            items: items.into_iter().map(BookResponse::from).collect(),
+            items: items
+                .into_iter()
+                .map(|book| BookResponse::from_book(book, include_owner_email))
+                .collect(),
         }
     }
+
+    pub fn book_ids(&self) -> Vec<BookId> {
+        self.items.iter().map(|book| book.id).collect()
+    }
+
+    pub fn mark_favorites(mut self, favorite_book_ids: &HashSet<BookId>) -> Self {
+        self.items = self
+            .items
+            .into_iter()
+            .map(|book| book.mark_favorites(favorite_book_ids))
+            .collect();
+        self
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -186,3 +625,484 @@ impl From<Checkout> for BookCheckoutResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::config::SearchField;
+
+    #[test]
+    fn test_patch_request_description() -> anyhow::Result<()> {
+        // 項目なし: 変更しない
+        let req: PatchBookRequest = serde_json::from_str(r#"{"title": "Rust"}"#)?;
+        assert_eq!(req.title.as_deref(), Some("Rust"));
+        assert_eq!(req.description, None);
+
+        // null: 値を消去する
+        let req: PatchBookRequest = serde_json::from_str(r#"{"description": null}"#)?;
+        assert_eq!(req.title, None);
+        assert_eq!(req.description, Some(None));
+
+        // 値あり: その値で更新する
+        let req: PatchBookRequest = serde_json::from_str(r#"{"description": "入門書"}"#)?;
+        assert_eq!(req.description, Some(Some("入門書".to_string())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_validation() {
+        let req = |title: &str, author: &str, description: &str| CreateBookRequest {
+            title: title.into(),
+            author: author.into(),
+            isbn: "978-4798061702".into(),
+            description: Some(description.into()),
+        };
+        let invalid_field = |req: CreateBookRequest| {
+            req.validate(&())
+                .unwrap_err()
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(req("Rust", "Author", "").validate(&()).is_ok());
+
+        // 空、または空白だけのタイトル・著者は受け付けない
+        assert_eq!(invalid_field(req("", "Author", "")), ["title"]);
+        assert_eq!(invalid_field(req(" \t", "Author", "")), ["title"]);
+        assert_eq!(invalid_field(req("Rust", "", "")), ["author"]);
+        assert_eq!(invalid_field(req("Rust", "　", "")), ["author"]);
+
+        // 上限はバイト数ではなく文字数で数える
+        let title = "あ".repeat(MAX_TITLE_LENGTH);
+        assert!(req(&title, "Author", "").validate(&()).is_ok());
+        let title = "a".repeat(MAX_TITLE_LENGTH + 1);
+        assert_eq!(invalid_field(req(&title, "Author", "")), ["title"]);
+
+        let author = "あ".repeat(MAX_AUTHOR_LENGTH);
+        assert!(req("Rust", &author, "").validate(&()).is_ok());
+        let author = "a".repeat(MAX_AUTHOR_LENGTH + 1);
+        assert_eq!(invalid_field(req("Rust", &author, "")), ["author"]);
+
+        let description = "あ".repeat(MAX_DESCRIPTION_LENGTH);
+        assert!(req("Rust", "Author", &description).validate(&()).is_ok());
+        let description = "a".repeat(MAX_DESCRIPTION_LENGTH + 1);
+        assert_eq!(
+            invalid_field(req("Rust", "Author", &description)),
+            ["description"]
+        );
+    }
+
+    #[test]
+    fn test_update_book_request_validation() {
+        let update = |title: &str, description: &str| UpdateBookRequest {
+            title: title.into(),
+            author: "Author".into(),
+            isbn: "isbn".into(),
+            description: Some(description.into()),
+            version: 1,
+        };
+        let patch = |title: Option<&str>, description: Option<&str>| PatchBookRequest {
+            title: title.map(Into::into),
+            author: None,
+            isbn: None,
+            description: Some(description.map(Into::into)),
+        };
+        let long_title = "a".repeat(MAX_TITLE_LENGTH + 1);
+        let long_description = "a".repeat(MAX_DESCRIPTION_LENGTH + 1);
+
+        // 更新時も登録時と同じく、空白だけのタイトルや列の長さを超える値は受け付けない
+        assert!(update("Rust", "").validate(&()).is_ok());
+        assert!(update(" ", "").validate(&()).is_err());
+        assert!(update(&long_title, "").validate(&()).is_err());
+        assert!(update("Rust", &long_description).validate(&()).is_err());
+
+        // PATCH では指定した項目だけを確かめる
+        assert!(patch(None, None).validate(&()).is_ok());
+        assert!(patch(Some("Rust"), Some("")).validate(&()).is_ok());
+        assert!(patch(Some("　"), None).validate(&()).is_err());
+        assert!(patch(Some(&long_title), None).validate(&()).is_err());
+        assert!(patch(None, Some(&long_description)).validate(&()).is_err());
+    }
+
+    #[test]
+    fn test_cursor_query() -> anyhow::Result<()> {
+        let cursor = |q: &str| -> anyhow::Result<AppResult<Option<Option<BookId>>>> {
+            let uri: axum::http::Uri = format!("/books?{q}").parse()?;
+            Ok(axum::extract::Query::<BookCursorQuery>::try_from_uri(&uri)?
+                .0
+                .cursor())
+        };
+        let book_id = BookId::new();
+
+        // 指定しなければ limit / offset で一覧し、空文字なら先頭から、ID ならその続きから一覧する
+        assert!(matches!(cursor("limit=10")?, Ok(None)));
+        assert!(matches!(cursor("cursor=")?, Ok(Some(None))));
+        assert!(
+            matches!(cursor(&format!("cursor={}", book_id.raw()))?, Ok(Some(Some(id))) if id == book_id)
+        );
+        assert!(matches!(
+            cursor("cursor=not-a-uuid")?,
+            Err(shared::error::AppError::ConvertToUuidError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_query_for_cursor() -> anyhow::Result<()> {
+        let query = |q: &str| -> anyhow::Result<BookListQuery> {
+            let uri: axum::http::Uri = format!("/books?{q}").parse()?;
+            Ok(axum::extract::Query::<BookListQuery>::try_from_uri(&uri)?.0)
+        };
+
+        // limit と、cursor と同じ並び順の指定だけは併用できる
+        assert!(query("limit=10&sort=createdAt&order=desc")?
+            .validate_for_cursor()
+            .is_ok());
+
+        // 絞り込みや並び替えは無視せずエラーにする
+        for q in [
+            "available=true",
+            "tag=rust",
+            "tags=rust,web",
+            "sort=title",
+            "offset=20",
+        ] {
+            let res = query(q)?.validate_for_cursor();
+            assert!(matches!(res, Err(AppError::ValidationError(_))), "{q}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_query_created_range() -> anyhow::Result<()> {
+        let query = |q: &str| -> anyhow::Result<BookListQuery> {
+            let uri: axum::http::Uri = format!("/books?{q}").parse()?;
+            Ok(axum::extract::Query::<BookListQuery>::try_from_uri(&uri)?.0)
+        };
+
+        let q = query("created_after=2024-01-01T00:00:00Z&created_before=2024-02-01T00:00:00Z")?;
+        assert!(q.validate(&()).is_ok());
+        let options = BookListOptions::from(q);
+        assert_eq!(options.created_after, Some("2024-01-01T00:00:00Z".parse()?));
+
+        // 片方だけなら常に有効。created_after が created_before より後の場合はエラー
+        assert!(query("created_before=2024-02-01T00:00:00Z")?
+            .validate(&())
+            .is_ok());
+        let err = query("created_after=2024-02-01T00:00:00Z&created_before=2024-01-01T00:00:00Z")?
+            .validate(&())
+            .unwrap_err();
+        assert!(err.to_string().contains("created_before"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_query_limit() -> anyhow::Result<()> {
+        let options = |q: &str| -> anyhow::Result<BookListOptions> {
+            let uri: axum::http::Uri = format!("/books?{q}").parse()?;
+            Ok(axum::extract::Query::<BookListQuery>::try_from_uri(&uri)?
+                .0
+                .into())
+        };
+
+        let opts = options("")?;
+        assert_eq!((opts.limit, opts.offset), (DEFAULT_LIMIT, 0));
+
+        let opts = options("limit=50&offset=10")?;
+        assert_eq!((opts.limit, opts.offset), (50, 10));
+
+        // 上限を超える limit は MAX_LIMIT に丸める
+        assert_eq!(options("limit=100000")?.limit, MAX_LIMIT);
+
+        assert!(!options("")?.only_available);
+        assert!(options("available=true")?.only_available);
+
+        let owner_id = UserId::new();
+        assert_eq!(options("")?.owned_by, None);
+        assert_eq!(
+            options(&format!("owned_by={}", owner_id.raw()))?.owned_by,
+            Some(owner_id)
+        );
+
+        // 並び替えの向きを省略した場合は項目ごとの既定の向きになる
+        let opts = options("")?;
+        assert_eq!(
+            (opts.sort, opts.order),
+            (BookSortKey::CreatedAt, SortOrder::Desc)
+        );
+        let opts = options("sort=title")?;
+        assert_eq!(
+            (opts.sort, opts.order),
+            (BookSortKey::Title, SortOrder::Asc)
+        );
+        let opts = options("sort=author&order=desc")?;
+        assert_eq!(
+            (opts.sort, opts.order),
+            (BookSortKey::Author, SortOrder::Desc)
+        );
+        assert!(options("sort=isbn").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_query_tags() -> anyhow::Result<()> {
+        let query = |q: &str| -> anyhow::Result<BookListQuery> {
+            let uri: axum::http::Uri = format!("/books?{q}").parse()?;
+            Ok(axum::extract::Query::<BookListQuery>::try_from_uri(&uri)?.0)
+        };
+
+        let opts = BookListOptions::from(query("")?);
+        assert!(opts.tags.is_empty());
+        assert_eq!(opts.tag_match, TagMatch::All);
+
+        // カンマ区切りのタグは小文字に揃え、空の要素は捨てる。tag も絞り込みに含める
+        let q = query("tags=SciFi,,classic&match=any&tag=Novel")?;
+        assert!(q.validate(&()).is_ok());
+        let opts = BookListOptions::from(q);
+        assert_eq!(opts.tags, vec!["novel", "scifi", "classic"]);
+        assert_eq!(opts.tag_match, TagMatch::Any);
+
+        assert!(query("tags=a&match=none").is_err());
+        let tags = ["a"; MAX_FILTER_TAGS + 1].join(",");
+        assert!(query(&format!("tags={tags}"))?.validate(&()).is_err());
+        let long = "a".repeat(51);
+        assert!(query(&format!("tags=a,{long}"))?.validate(&()).is_err());
+
+        Ok(())
+    }
+
+    fn search_config() -> SearchConfig {
+        SearchConfig {
+            max_query_length: 30,
+            max_terms: 3,
+            fields: SearchField::ALL.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_search_query_length() {
+        let query = |q: &str| BookSearchQuery { q: q.into() };
+
+        assert!(query("").validate(&search_config()).is_ok());
+        assert!(query(&"a".repeat(30)).validate(&search_config()).is_ok());
+        assert!(query(&"a".repeat(31)).validate(&search_config()).is_err());
+        // マルチバイト文字もバイト数ではなく文字数で数える
+        assert!(query(&"あ".repeat(30)).validate(&search_config()).is_ok());
+
+        assert!(query("rust web app").validate(&search_config()).is_ok());
+        assert!(query("rust web app book")
+            .validate(&search_config())
+            .is_err());
+    }
+
+    #[test]
+    fn test_search_query_metacharacters() {
+        let query = BookSearchQuery {
+            q: "rust&!(web) 'app':* | ) 入門".into(),
+        };
+        assert_eq!(query.terms(), vec!["rust", "web", "app", "入門"]);
+        // 記号で区切られた単語もそれぞれ 1 語として数える
+        assert!(query.validate(&search_config()).is_err());
+
+        // 記号だけの単語は単語数に数えない
+        let query = BookSearchQuery {
+            q: "rust & | ! web".into(),
+        };
+        assert!(query.validate(&search_config()).is_ok());
+        assert_eq!(query.terms(), vec!["rust", "web"]);
+    }
+
+    fn sample_book() -> Book {
+        Book {
+            id: BookId::new(),
+            title: "Rust".into(),
+            author: "Ferris".into(),
+            isbn: "978-4-00-000000-0".into(),
+            description: None,
+            owner: kernel::model::user::BookOwner {
+                id: UserId::new(),
+                name: "Eleazar Fig".into(),
+                email: "eleazar.fig@example.com".into(),
+            },
+            checkout: None,
+            tags: vec![],
+            version: 1,
+            created_at: "2024-01-01T00:00:00Z".parse().unwrap(),
+            updated_at: "2024-01-02T09:30:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_owner_email_visibility() -> anyhow::Result<()> {
+        // 一般ユーザーには所有者の名前だけを返す
+        let json = serde_json::to_value(BookResponse::from_book(sample_book(), false))?;
+        assert_eq!(json["owner"]["name"], "Eleazar Fig");
+        assert!(json["owner"].get("email").is_none());
+
+        // 管理者には連絡用のメールアドレスも返す
+        let json = serde_json::to_value(BookResponse::from_book(sample_book(), true))?;
+        assert_eq!(json["owner"]["name"], "Eleazar Fig");
+        assert_eq!(json["owner"]["email"], "eleazar.fig@example.com");
+
+        // 一覧でも同様に切り替わる
+        let list = || PaginatedList {
+            total: Some(1),
+            limit: 20,
+            offset: 0,
+            items: vec![sample_book()],
+        };
+        let json = serde_json::to_value(PaginatedBookResponse::from(list()))?;
+        assert!(json["items"][0]["owner"].get("email").is_none());
+        let json = serde_json::to_value(PaginatedBookResponse::from_list(list(), true))?;
+        assert_eq!(
+            json["items"][0]["owner"]["email"],
+            "eleazar.fig@example.com"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_available() -> anyhow::Result<()> {
+        let json = serde_json::to_value(BookResponse::from(sample_book()))?;
+        assert_eq!(json["isAvailable"], true);
+        // 日時は RFC 3339 形式で返す
+        assert_eq!(json["createdAt"], "2024-01-01T00:00:00Z");
+        assert_eq!(json["updatedAt"], "2024-01-02T09:30:00Z");
+
+        // 貸出中の蔵書は借りられない
+        let book = Book {
+            checkout: Some(Checkout {
+                checkout_id: CheckoutId::new(),
+                checked_out_by: kernel::model::user::CheckoutUser {
+                    id: UserId::new(),
+                    name: "Adam Smith".into(),
+                },
+                checked_out_at: Utc::now(),
+            }),
+            ..sample_book()
+        };
+        let json = serde_json::to_value(BookResponse::from(book))?;
+        assert_eq!(json["isAvailable"], false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_without_description() -> anyhow::Result<()> {
+        let create = |json: &str| -> anyhow::Result<CreateBook> {
+            let req: CreateBookRequest = serde_json::from_str(json)?;
+            Ok(req.into())
+        };
+
+        // 省略した場合も空文字列の場合も、説明のない蔵書になる
+        let book = create(r#"{"title": "t", "author": "a", "isbn": "i"}"#)?;
+        assert_eq!(book.description, None);
+        let book = create(r#"{"title": "t", "author": "a", "isbn": "i", "description": ""}"#)?;
+        assert_eq!(book.description, None);
+        let book = create(r#"{"title": "t", "author": "a", "isbn": "i", "description": "d"}"#)?;
+        assert_eq!(book.description.as_deref(), Some("d"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_patch_request_validation() {
+        let req: PatchBookRequest = serde_json::from_str(r#"{}"#).unwrap();
+        assert!(req.validate(&()).is_ok());
+
+        let req: PatchBookRequest = serde_json::from_str(r#"{"title": ""}"#).unwrap();
+        assert!(req.validate(&()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bad_request_vs_unprocessable() -> anyhow::Result<()> {
+        use crate::extractor::Path;
+        use axum::{
+            body::Body,
+            http::{header, Request, StatusCode},
+            routing::{get, post},
+            Json, Router,
+        };
+        use shared::error::{AppError, AppResult};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/books",
+                post(|Json(req): Json<CreateBookRequest>| async move {
+                    req.validate(&())?;
+                    AppResult::Ok(StatusCode::CREATED)
+                }),
+            )
+            .route(
+                "/books/:book_id",
+                get(|Path(_): Path<BookId>| async { StatusCode::OK }),
+            )
+            .route(
+                "/books/:book_id/raw",
+                get(|Path(id): Path<String>| async move {
+                    id.parse::<BookId>()?;
+                    Ok::<_, AppError>(StatusCode::OK)
+                }),
+            );
+        let post_book = |body: &'static str| {
+            Request::post("/books")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        let status = |req: Request<Body>| {
+            let app = app.clone();
+            async move { app.oneshot(req).await.map(|res| res.status()) }
+        };
+
+        // 構文として解釈できない入力は 400
+        assert_eq!(
+            status(post_book(r#"{"title": "#)?).await?,
+            StatusCode::BAD_REQUEST
+        );
+        let res = app
+            .clone()
+            .oneshot(Request::get("/books/not-a-uuid").body(Body::empty())?)
+            .await?;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(res.into_body(), usize::MAX).await?;
+        let json: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(json["error"]["code"], "invalid_path_parameter");
+        assert!(json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("UUID parsing failed"));
+        assert_eq!(
+            status(Request::get("/books/not-a-uuid/raw").body(Body::empty())?).await?,
+            StatusCode::BAD_REQUEST
+        );
+
+        // 構文は正しいが値が受け付けられない入力は 422
+        assert_eq!(
+            status(post_book(
+                r#"{"title": "", "author": "a", "isbn": "i", "description": ""}"#
+            )?)
+            .await?,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(post_book(r#"{"title": "t"}"#)?).await?,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        // 正しい入力
+        assert_eq!(
+            status(post_book(
+                r#"{"title": "t", "author": "a", "isbn": "i", "description": ""}"#
+            )?)
+            .await?,
+            StatusCode::CREATED
+        );
+
+        Ok(())
+    }
+}
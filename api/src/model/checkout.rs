@@ -1,9 +1,17 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use garde::Validate;
 use kernel::model::{
-    checkout::{Checkout, CheckoutBook},
+    book::BookListOptions,
+    checkout::{
+        Checkout, CheckoutBatchItem, CheckoutBook, CheckoutListFilter, CheckoutOutcome, DailyCount,
+        LostCheckout, OverdueCheckout,
+    },
     id::{BookId, CheckoutId, UserId},
+    list::PaginatedList,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use super::book::{default_limit, MAX_LIMIT};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,6 +27,74 @@ impl From<Vec<Checkout>> for CheckoutsResponse {
     }
 }
 
+// 全蔵書の貸出を一覧する際のクエリ
+#[derive(Debug, Deserialize, Validate)]
+pub struct CheckoutListQuery {
+    #[garde(range(min = 0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[garde(range(min = 0))]
+    #[serde(default)]
+    pub offset: i64,
+    // true の場合は返却済みの貸出を含めない
+    #[garde(skip)]
+    #[serde(default)]
+    pub active: bool,
+    #[garde(skip)]
+    pub user_id: Option<UserId>,
+    #[garde(skip)]
+    pub book_id: Option<BookId>,
+}
+
+impl CheckoutListQuery {
+    pub fn into_filter_and_options(self) -> (CheckoutListFilter, BookListOptions) {
+        let CheckoutListQuery {
+            limit,
+            offset,
+            active,
+            user_id,
+            book_id,
+        } = self;
+        let filter = CheckoutListFilter {
+            active_only: active,
+            user_id,
+            book_id,
+        };
+        let options = BookListOptions {
+            limit: limit.min(MAX_LIMIT),
+            offset,
+            ..Default::default()
+        };
+        (filter, options)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedCheckoutResponse {
+    pub total: Option<i64>,
+    pub limit: i64,
+    pub offset: i64,
+    pub items: Vec<CheckoutResponse>,
+}
+
+impl From<PaginatedList<Checkout>> for PaginatedCheckoutResponse {
+    fn from(value: PaginatedList<Checkout>) -> Self {
+        let PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        } = value;
+        Self {
+            total,
+            limit,
+            offset,
+            items: items.into_iter().map(CheckoutResponse::from).collect(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutResponse {
@@ -73,3 +149,267 @@ impl From<CheckoutBook> for CheckoutBookResponse {
         }
     }
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LostCheckoutsResponse {
+    pub items: Vec<LostCheckoutResponse>,
+}
+
+impl From<Vec<LostCheckout>> for LostCheckoutsResponse {
+    fn from(value: Vec<LostCheckout>) -> Self {
+        Self {
+            items: value.into_iter().map(LostCheckoutResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LostCheckoutResponse {
+    pub id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub checked_out_by: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub days_overdue: i64,
+}
+
+impl From<LostCheckout> for LostCheckoutResponse {
+    fn from(value: LostCheckout) -> Self {
+        let LostCheckout {
+            checkout_id,
+            book_id,
+            title,
+            checked_out_by,
+            user_name,
+            email,
+            checked_out_at,
+            days_overdue,
+        } = value;
+        Self {
+            id: checkout_id,
+            book_id,
+            title,
+            checked_out_by,
+            user_name,
+            email,
+            checked_out_at,
+            days_overdue,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdueCheckoutsResponse {
+    pub items: Vec<OverdueCheckoutResponse>,
+}
+
+impl From<Vec<OverdueCheckout>> for OverdueCheckoutsResponse {
+    fn from(value: Vec<OverdueCheckout>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(OverdueCheckoutResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverdueCheckoutResponse {
+    pub id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub checked_out_by: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+impl From<OverdueCheckout> for OverdueCheckoutResponse {
+    fn from(value: OverdueCheckout) -> Self {
+        let OverdueCheckout {
+            checkout_id,
+            book_id,
+            title,
+            checked_out_by,
+            user_name,
+            email,
+            checked_out_at,
+            due_at,
+        } = value;
+        Self {
+            id: checkout_id,
+            book_id,
+            title,
+            checked_out_by,
+            user_name,
+            email,
+            checked_out_at,
+            due_at,
+        }
+    }
+}
+
+// 複数の蔵書をまとめて貸し出すリクエスト。
+// bestEffort を true にすると、貸し出せない蔵書があっても残りの蔵書は貸し出す
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutBatchRequest {
+    #[garde(length(min = 1))]
+    pub book_ids: Vec<BookId>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub best_effort: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutBatchResponse {
+    pub items: Vec<CheckoutBatchItemResponse>,
+}
+
+impl From<Vec<CheckoutBatchItem>> for CheckoutBatchResponse {
+    fn from(value: Vec<CheckoutBatchItem>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(CheckoutBatchItemResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutBatchItemResponse {
+    pub book_id: BookId,
+    pub status: CheckoutBatchStatus,
+    pub checkout_id: Option<CheckoutId>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckoutBatchStatus {
+    CheckedOut,
+    NotFound,
+    AlreadyCheckedOut,
+    Reserved,
+    LimitExceeded,
+}
+
+impl From<CheckoutBatchItem> for CheckoutBatchItemResponse {
+    fn from(value: CheckoutBatchItem) -> Self {
+        let CheckoutBatchItem { book_id, outcome } = value;
+        let (status, checkout_id) = match outcome {
+            CheckoutOutcome::CheckedOut(checkout_id) => {
+                (CheckoutBatchStatus::CheckedOut, Some(checkout_id))
+            }
+            CheckoutOutcome::NotFound => (CheckoutBatchStatus::NotFound, None),
+            CheckoutOutcome::AlreadyCheckedOut => (CheckoutBatchStatus::AlreadyCheckedOut, None),
+            CheckoutOutcome::Reserved => (CheckoutBatchStatus::Reserved, None),
+            CheckoutOutcome::LimitExceeded => (CheckoutBatchStatus::LimitExceeded, None),
+        };
+        Self {
+            book_id,
+            status,
+            checkout_id,
+        }
+    }
+}
+
+// 一度に集計できる期間の上限（日数）
+const MAX_DAILY_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DailyCheckoutsQuery {
+    #[garde(skip)]
+    pub from: NaiveDate,
+    #[garde(custom(validate_daily_range(&self.from)))]
+    pub to: NaiveDate,
+}
+
+fn validate_daily_range(from: &NaiveDate) -> impl FnOnce(&NaiveDate, &()) -> garde::Result + '_ {
+    move |to, _| {
+        if to < from {
+            return Err(garde::Error::new("must not be before `from`"));
+        }
+        if (*to - *from).num_days() >= MAX_DAILY_RANGE_DAYS {
+            return Err(garde::Error::new(format!(
+                "range must be at most {MAX_DAILY_RANGE_DAYS} days"
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyCheckoutsResponse {
+    pub items: Vec<DailyCountResponse>,
+}
+
+impl From<Vec<DailyCount>> for DailyCheckoutsResponse {
+    fn from(value: Vec<DailyCount>) -> Self {
+        Self {
+            items: value.into_iter().map(DailyCountResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyCountResponse {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+impl From<DailyCount> for DailyCountResponse {
+    fn from(value: DailyCount) -> Self {
+        let DailyCount { date, count } = value;
+        Self { date, count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_checkouts_query_range() -> anyhow::Result<()> {
+        let query =
+            |q: &str| -> anyhow::Result<DailyCheckoutsQuery> { Ok(serde_json::from_str(q)?) };
+
+        assert!(query(r#"{"from": "2024-11-01", "to": "2024-11-01"}"#)?
+            .validate(&())
+            .is_ok());
+        assert!(query(r#"{"from": "2024-01-01", "to": "2024-12-31"}"#)?
+            .validate(&())
+            .is_ok());
+        // 終了日が開始日より前
+        assert!(query(r#"{"from": "2024-11-02", "to": "2024-11-01"}"#)?
+            .validate(&())
+            .is_err());
+        // 上限の日数を超える
+        assert!(query(r#"{"from": "2024-01-01", "to": "2025-01-01"}"#)?
+            .validate(&())
+            .is_err());
+
+        let res = DailyCheckoutsResponse::from(vec![DailyCount {
+            date: NaiveDate::from_ymd_opt(2024, 11, 1).unwrap(),
+            count: 2,
+        }]);
+        assert_eq!(
+            serde_json::to_value(res)?,
+            serde_json::json!({ "items": [{ "date": "2024-11-01", "count": 2 }] })
+        );
+
+        Ok(())
+    }
+}
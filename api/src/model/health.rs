@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use shared::env::Environment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyStatus {
+    Ok,
+    Unavailable,
+}
+
+// 依存先ごとの稼働状況と、稼働していない依存先の一覧
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub dependencies: BTreeMap<&'static str, DependencyStatus>,
+    pub failed: Vec<&'static str>,
+}
+
+impl ReadinessResponse {
+    pub fn is_ready(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl FromIterator<(&'static str, bool)> for ReadinessResponse {
+    fn from_iter<T: IntoIterator<Item = (&'static str, bool)>>(iter: T) -> Self {
+        let mut dependencies = BTreeMap::new();
+        let mut failed = Vec::new();
+        for (name, ready) in iter {
+            if ready {
+                dependencies.insert(name, DependencyStatus::Ok);
+            } else {
+                dependencies.insert(name, DependencyStatus::Unavailable);
+                failed.push(name);
+            }
+        }
+        Self {
+            dependencies,
+            failed,
+        }
+    }
+}
+
+// デプロイされているビルドを確認するための情報。設定値などの機微な情報は含めない
+#[derive(Debug, Serialize)]
+pub struct HealthInfoResponse {
+    pub version: &'static str,
+    pub commit: &'static str,
+    pub environment: String,
+}
+
+impl HealthInfoResponse {
+    // コミットハッシュは build.rs で埋め込む
+    pub fn new(environment: Environment) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: env!("GIT_COMMIT_HASH"),
+            environment: environment.to_string(),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use axum::{
+    http::{
+        header::{HeaderName, HeaderValue, LINK},
+        Uri,
+    },
+    response::{IntoResponseParts, ResponseParts},
+};
+use shared::error::AppError;
+
+pub const X_TOTAL_COUNT: &str = "x-total-count";
+
+// 一覧のレスポンスに付与する X-Total-Count と Link（RFC 8288）ヘッダー。
+// ボディを解釈しない汎用の HTTP クライアントでもページをたどれるようにする。
+// 総件数を数えていない（total が None の）場合は X-Total-Count と last を返さず、
+// ページが件数分埋まっていれば続きがあるとみなして next を返す
+pub struct PaginationHeaders {
+    total: Option<i64>,
+    links: Vec<(&'static str, String)>,
+}
+
+impl PaginationHeaders {
+    // uri はリクエストされた URI。limit / offset 以外のクエリはそのまま引き継ぐ。
+    // page_len はこのページで返す件数
+    pub fn new(uri: &Uri, total: Option<i64>, limit: i64, offset: i64, page_len: usize) -> Self {
+        let path = uri.path();
+        let params: Vec<&str> = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| {
+                let key = param.split('=').next().unwrap_or_default();
+                !param.is_empty() && key != "limit" && key != "offset"
+            })
+            .collect();
+        let link = |offset: i64| {
+            let mut query = params.clone();
+            let page = format!("limit={limit}&offset={offset}");
+            query.push(&page);
+            format!("{path}?{}", query.join("&"))
+        };
+
+        let mut links = vec![("first", link(0))];
+        if limit > 0 {
+            if offset > 0 {
+                links.push(("prev", link((offset - limit).max(0))));
+            }
+            // 最終ページでは next を返さない
+            let has_next = match total {
+                Some(total) => offset + limit < total,
+                None => page_len as i64 >= limit,
+            };
+            if has_next {
+                links.push(("next", link(offset + limit)));
+            }
+            if let Some(total) = total {
+                let last = if total > 0 {
+                    (total - 1) / limit * limit
+                } else {
+                    0
+                };
+                links.push(("last", link(last)));
+            }
+        }
+
+        Self { total, links }
+    }
+}
+
+impl IntoResponseParts for PaginationHeaders {
+    type Error = AppError;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let link = self
+            .links
+            .iter()
+            .map(|(rel, url)| format!("<{url}>; rel=\"{rel}\""))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let headers = res.headers_mut();
+        if let Some(total) = self.total {
+            headers.insert(
+                HeaderName::from_static(X_TOTAL_COUNT),
+                HeaderValue::from(total),
+            );
+        }
+        // パスとクエリはリクエストの URI から組み立てるため、ヘッダーとして不正な値にはならない
+        if let Ok(link) = HeaderValue::from_str(&link) {
+            headers.insert(LINK, link);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(uri: &str, total: i64, limit: i64, offset: i64) -> Vec<(&'static str, String)> {
+        let page_len = (total - offset).clamp(0, limit) as usize;
+        PaginationHeaders::new(&uri.parse().unwrap(), Some(total), limit, offset, page_len).links
+    }
+
+    #[test]
+    fn test_pagination_links() {
+        assert_eq!(
+            links("/api/v1/books?limit=20&offset=20&sort=title", 50, 20, 20),
+            vec![
+                ("first", "/api/v1/books?sort=title&limit=20&offset=0".into()),
+                ("prev", "/api/v1/books?sort=title&limit=20&offset=0".into()),
+                ("next", "/api/v1/books?sort=title&limit=20&offset=40".into()),
+                ("last", "/api/v1/books?sort=title&limit=20&offset=40".into()),
+            ]
+        );
+
+        // 最初のページには prev、最後のページには next がない
+        assert_eq!(
+            links("/api/v1/books", 50, 20, 0),
+            vec![
+                ("first", "/api/v1/books?limit=20&offset=0".into()),
+                ("next", "/api/v1/books?limit=20&offset=20".into()),
+                ("last", "/api/v1/books?limit=20&offset=40".into()),
+            ]
+        );
+        assert_eq!(
+            links("/api/v1/books?offset=40", 50, 20, 40),
+            vec![
+                ("first", "/api/v1/books?limit=20&offset=0".into()),
+                ("prev", "/api/v1/books?limit=20&offset=20".into()),
+                ("last", "/api/v1/books?limit=20&offset=40".into()),
+            ]
+        );
+
+        // 1件もない場合は first と last が同じページを指す
+        assert_eq!(
+            links("/api/v1/books", 0, 20, 0),
+            vec![
+                ("first", "/api/v1/books?limit=20&offset=0".into()),
+                ("last", "/api/v1/books?limit=20&offset=0".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pagination_links_without_total() {
+        let links = |page_len: usize| {
+            PaginationHeaders::new(&"/api/v1/books".parse().unwrap(), None, 20, 20, page_len).links
+        };
+
+        // ページが埋まっていれば next を返す。総件数が分からないため last は返さない
+        assert_eq!(
+            links(20),
+            vec![
+                ("first", "/api/v1/books?limit=20&offset=0".into()),
+                ("prev", "/api/v1/books?limit=20&offset=0".into()),
+                ("next", "/api/v1/books?limit=20&offset=40".into()),
+            ]
+        );
+        assert_eq!(
+            links(5),
+            vec![
+                ("first", "/api/v1/books?limit=20&offset=0".into()),
+                ("prev", "/api/v1/books?limit=20&offset=0".into()),
+            ]
+        );
+    }
+}
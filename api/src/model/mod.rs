@@ -1,4 +1,9 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod health;
+pub mod list;
+pub mod reservation;
+pub mod stats;
+pub mod tag;
 pub mod user;
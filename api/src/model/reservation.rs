@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, ReservationId, UserId},
+    reservation::{Reservation, ReservationPosition},
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationResponse {
+    pub id: ReservationId,
+    pub book_id: BookId,
+    // 順番待ちの中での位置。先頭が 1
+    pub position: i64,
+}
+
+impl From<ReservationPosition> for ReservationResponse {
+    fn from(value: ReservationPosition) -> Self {
+        let ReservationPosition {
+            reservation_id,
+            book_id,
+            position,
+            ..
+        } = value;
+        Self {
+            id: reservation_id,
+            book_id,
+            position,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationQueueResponse {
+    pub items: Vec<QueuedReservationResponse>,
+}
+
+impl From<Vec<Reservation>> for ReservationQueueResponse {
+    fn from(value: Vec<Reservation>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(QueuedReservationResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedReservationResponse {
+    pub id: ReservationId,
+    pub position: i64,
+    pub user_id: UserId,
+    pub user_name: String,
+    pub reserved_at: DateTime<Utc>,
+}
+
+impl From<Reservation> for QueuedReservationResponse {
+    fn from(value: Reservation) -> Self {
+        let Reservation {
+            reservation_id,
+            reserved_by,
+            position,
+            reserved_at,
+            ..
+        } = value;
+        Self {
+            id: reservation_id,
+            position,
+            user_id: reserved_by.id,
+            user_name: reserved_by.name,
+            reserved_at,
+        }
+    }
+}
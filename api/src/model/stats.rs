@@ -0,0 +1,25 @@
+use kernel::model::stats::LibraryStats;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStatsResponse {
+    pub total_books: i64,
+    pub active_checkouts: i64,
+    pub total_users: i64,
+}
+
+impl From<LibraryStats> for LibraryStatsResponse {
+    fn from(value: LibraryStats) -> Self {
+        let LibraryStats {
+            total_books,
+            active_checkouts,
+            total_users,
+        } = value;
+        Self {
+            total_books,
+            active_checkouts,
+            total_users,
+        }
+    }
+}
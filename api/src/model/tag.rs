@@ -0,0 +1,78 @@
+use kernel::model::tag::Tag;
+use serde::Serialize;
+use shared::error::{AppError, AppResult};
+
+// tags テーブルの name 列の長さ（文字数）に合わせた上限
+const MAX_TAG_LENGTH: usize = 50;
+
+// タグは大文字・小文字や前後の空白を区別しない。保存・検索の前に小文字に揃える
+pub fn normalize_tag_name(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+pub fn validate_tag_name(value: &str, _: &()) -> garde::Result {
+    let name = normalize_tag_name(value);
+    if name.is_empty() {
+        return Err(garde::Error::new("must not be blank"));
+    }
+    if name.chars().count() > MAX_TAG_LENGTH {
+        return Err(garde::Error::new(format!(
+            "length is greater than {MAX_TAG_LENGTH}"
+        )));
+    }
+    Ok(())
+}
+
+// パスパラメータで受け取ったタグ名を検証し、正規化した名前を返す
+pub fn parse_tag_name(value: &str) -> AppResult<String> {
+    validate_tag_name(value, &()).map_err(|e| AppError::invalid_field("tag", e.message()))?;
+    Ok(normalize_tag_name(value))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagsResponse {
+    pub items: Vec<TagResponse>,
+}
+
+impl From<Vec<Tag>> for TagsResponse {
+    fn from(value: Vec<Tag>) -> Self {
+        Self {
+            items: value.into_iter().map(TagResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagResponse {
+    pub name: String,
+    pub book_count: i64,
+}
+
+impl From<Tag> for TagResponse {
+    fn from(value: Tag) -> Self {
+        let Tag { name, book_count } = value;
+        Self { name, book_count }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_name() {
+        assert_eq!(parse_tag_name("  Sci-Fi ").unwrap(), "sci-fi");
+        assert_eq!(parse_tag_name("小説").unwrap(), "小説");
+        assert!(matches!(
+            parse_tag_name("   "),
+            Err(AppError::ValidationError(_))
+        ));
+        assert!(parse_tag_name(&"a".repeat(MAX_TAG_LENGTH)).is_ok());
+        assert!(matches!(
+            parse_tag_name(&"a".repeat(MAX_TAG_LENGTH + 1)),
+            Err(AppError::ValidationError(_))
+        ));
+    }
+}
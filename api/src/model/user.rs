@@ -5,10 +5,12 @@ use kernel::model::{
     role::Role,
     user::{
         event::{CreateUser, UpdateUserPassword, UpdateUserRole},
-        User,
+        User, UserDetail,
     },
 };
 use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+use std::str::FromStr;
 use strum::VariantNames;
 
 #[derive(Serialize, Deserialize, VariantNames)]
@@ -68,15 +70,49 @@ impl From<User> for UserResponse {
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDetailResponse {
+    #[serde(flatten)]
+    pub user: UserResponse,
+    // 貸出中（未返却）の冊数
+    pub active_checkout_count: i64,
+}
+
+impl From<UserDetail> for UserDetailResponse {
+    fn from(value: UserDetail) -> Self {
+        let UserDetail {
+            user,
+            active_checkout_count,
+        } = value;
+        Self {
+            user: UserResponse::from(user),
+            active_checkout_count,
+        }
+    }
+}
+
 #[derive(Deserialize, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserPasswordRequest {
     #[garde(length(min = 1))]
     current_password: String,
-    #[garde(length(min = 1))]
+    #[garde(length(min = MIN_PASSWORD_LENGTH), custom(validate_password_complexity))]
     new_password: String,
 }
 
+// 変更後のパスワードには英字と数字をそれぞれ1文字以上含める
+fn validate_password_complexity(value: &str, _: &()) -> garde::Result {
+    let has_alphabetic = value.chars().any(|c| c.is_alphabetic());
+    let has_numeric = value.chars().any(|c| c.is_numeric());
+    if !has_alphabetic || !has_numeric {
+        return Err(garde::Error::new(
+            "must contain at least one letter and one digit",
+        ));
+    }
+    Ok(())
+}
+
 #[derive(new)]
 pub struct UpdateUserPasswordRequestWithUserId(UserId, UpdateUserPasswordRequest);
 impl From<UpdateUserPasswordRequestWithUserId> for UpdateUserPassword {
@@ -103,10 +139,13 @@ pub struct CreateUserRequest {
     name: String,
     #[garde(email)]
     email: String,
-    #[garde(length(min = 1))]
+    #[garde(length(min = MIN_PASSWORD_LENGTH))]
     password: String,
 }
 
+// 登録・変更時のパスワードの最小文字数
+const MIN_PASSWORD_LENGTH: usize = 8;
+
 impl From<CreateUserRequest> for CreateUser {
     fn from(value: CreateUserRequest) -> Self {
         let CreateUserRequest {
@@ -125,18 +164,20 @@ impl From<CreateUserRequest> for CreateUser {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserRoleRequest {
-    role: RoleName,
+    // 未知のロール名もデシリアライズ時には弾かず、ValidationError として返す
+    role: String,
 }
 
 #[derive(new)]
 pub struct UpdateUserRoleRequestWithUserId(UserId, UpdateUserRoleRequest);
-impl From<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
-    fn from(value: UpdateUserRoleRequestWithUserId) -> Self {
+impl TryFrom<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
+    type Error = AppError;
+
+    fn try_from(value: UpdateUserRoleRequestWithUserId) -> AppResult<Self> {
         let UpdateUserRoleRequestWithUserId(user_id, UpdateUserRoleRequest { role }) = value;
-        Self {
-            user_id,
-            role: Role::from(role),
-        }
+        let role =
+            Role::from_str(&role).map_err(|_| AppError::invalid_field("role", "unknown role"))?;
+        Ok(Self { user_id, role })
     }
 }
 
@@ -145,12 +186,30 @@ impl From<UpdateUserRoleRequestWithUserId> for UpdateUserRole {
 pub struct BookOwner {
     pub id: UserId,
     pub name: String,
+    // 管理者向けのレスポンスにだけ含める
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
 }
 
 impl From<kernel::model::user::BookOwner> for BookOwner {
     fn from(value: kernel::model::user::BookOwner) -> Self {
-        let kernel::model::user::BookOwner { id, name } = value;
-        Self { id, name }
+        let kernel::model::user::BookOwner { id, name, .. } = value;
+        Self {
+            id,
+            name,
+            email: None,
+        }
+    }
+}
+
+impl BookOwner {
+    pub fn with_email(value: kernel::model::user::BookOwner) -> Self {
+        let kernel::model::user::BookOwner { id, name, email } = value;
+        Self {
+            id,
+            name,
+            email: Some(email),
+        }
     }
 }
 
@@ -167,3 +226,65 @@ impl From<kernel::model::user::CheckoutUser> for CheckoutUser {
         Self { id, name }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_user_request_validation() -> anyhow::Result<()> {
+        let req = |email: &str, password: &str| -> anyhow::Result<CreateUserRequest> {
+            Ok(serde_json::from_value(serde_json::json!({
+                "name": "Test User",
+                "email": email,
+                "password": password,
+            }))?)
+        };
+
+        assert!(req("test.user@example.com", "password")?
+            .validate(&())
+            .is_ok());
+        assert!(req("not-an-email", "password")?.validate(&()).is_err());
+        assert!(req("test.user@example.com", "short")?
+            .validate(&())
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_password_request_validation() -> anyhow::Result<()> {
+        let req = |new_password: &str| -> anyhow::Result<UpdateUserPasswordRequest> {
+            Ok(serde_json::from_value(serde_json::json!({
+                "currentPassword": "current_password",
+                "newPassword": new_password,
+            }))?)
+        };
+
+        assert!(req("new_passw0rd")?.validate(&()).is_ok());
+        // 短すぎるパスワード、英字または数字を含まないパスワードは受け付けない
+        assert!(req("pa55")?.validate(&()).is_err());
+        assert!(req("new_password")?.validate(&()).is_err());
+        assert!(req("1234567890")?.validate(&()).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_role_request() -> anyhow::Result<()> {
+        let update = |role: &str| -> anyhow::Result<AppResult<UpdateUserRole>> {
+            let req: UpdateUserRoleRequest =
+                serde_json::from_value(serde_json::json!({ "role": role }))?;
+            Ok(UpdateUserRoleRequestWithUserId::new(UserId::new(), req).try_into())
+        };
+
+        assert_eq!(update("Admin")?.unwrap().role, Role::Admin);
+        assert_eq!(update("User")?.unwrap().role, Role::User);
+        assert!(matches!(
+            update("Owner")?,
+            Err(AppError::ValidationError(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -1,27 +1,51 @@
 use axum::{
-    routing::{delete, get, post, put},
+    routing::{delete, get, patch, post, put},
     Router,
 };
-use kernel::model::checkout;
 use registry::AppRegistry;
 
 use crate::handler::{
-    book::{delete_book, register_book, show_book, show_book_list, update_book},
-    checkout::{checkout_book, checkout_history, return_book, show_checked_out_list},
+    book::{
+        delete_book, patch_book, register_book, register_books, restore_book, search_books,
+        show_book, show_book_by_isbn, show_book_list, show_my_book_list, update_book,
+    },
+    checkout::{
+        cancel_checkout, checkout_book, checkout_history, return_book, show_checked_out_list,
+    },
+    favorite::{add_favorite, remove_favorite},
+    reservation::{cancel_reservation, reserve_book, show_reservation_queue},
+    tag::{attach_tag, detach_tag},
 };
 
 pub fn build_book_routers() -> Router<AppRegistry> {
     let books_routers = Router::new()
         .route("/", post(register_book))
         .route("/", get(show_book_list))
+        .route("/bulk", post(register_books))
+        .route("/mine", get(show_my_book_list))
+        .route("/search", get(search_books))
+        .route("/by-isbn/:isbn", get(show_book_by_isbn))
         .route("/:book_id", get(show_book))
         .route("/:book_id", put(update_book))
-        .route("/:book_id", delete(delete_book));
+        .route("/:book_id", patch(patch_book))
+        .route("/:book_id", delete(delete_book))
+        .route("/:book_id/restore", post(restore_book))
+        .route("/:book_id/favorite", post(add_favorite))
+        .route("/:book_id/favorite", delete(remove_favorite))
+        .route("/:book_id/reservations", post(reserve_book))
+        .route("/:book_id/reservations", get(show_reservation_queue))
+        .route("/:book_id/reservations", delete(cancel_reservation))
+        .route("/:book_id/tags/:tag", put(attach_tag))
+        .route("/:book_id/tags/:tag", delete(detach_tag));
 
     let checkout_router = Router::new()
         .route("/checkouts", get(show_checked_out_list))
         .route("/:book_id/checkouts", post(checkout_book))
-        .route("/:book_id/checkouts/:checkout_id/returned", put(return_book))
+        .route("/:book_id/checkouts/:checkout_id", delete(cancel_checkout))
+        .route(
+            "/:book_id/checkouts/:checkout_id/returned",
+            put(return_book),
+        )
         .route("/:book_id/checkout-history", get(checkout_history));
 
     Router::new().nest("/books", books_routers.merge(checkout_router))
@@ -0,0 +1,19 @@
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use registry::AppRegistry;
+
+use crate::handler::checkout::{
+    checkout_books, show_checkout_list, show_lost_checkouts, show_overdue_checkouts,
+};
+
+pub fn build_checkout_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route("/", get(show_checkout_list))
+        .route("/batch", post(checkout_books))
+        .route("/lost", get(show_lost_checkouts))
+        .route("/overdue", get(show_overdue_checkouts));
+
+    Router::new().nest("/checkouts", routers)
+}
@@ -1,11 +1,17 @@
 use axum::{routing::get, Router};
 use registry::AppRegistry;
 
-use crate::handler::health::{health_check, health_check_db};
+use crate::handler::health::{health_check, health_check_db, health_info, readiness_check};
 
 pub fn build_health_check_routers() -> Router<AppRegistry> {
     let routers = Router::new()
         .route("/", get(health_check))
-        .route("/db", get(health_check_db));
+        .route("/db", get(health_check_db))
+        .route("/info", get(health_info));
     Router::new().nest("/health", routers)
 }
+
+// ロードバランサやオーケストレータから叩かれる想定のため、API のバージョンとは切り離してルート直下に置く
+pub fn build_readiness_routers() -> Router<AppRegistry> {
+    Router::new().route("/readyz", get(readiness_check))
+}
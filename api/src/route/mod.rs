@@ -1,5 +1,8 @@
 pub mod auth;
 pub mod book;
+pub mod checkout;
 pub mod health;
+pub mod stats;
+pub mod tag;
 pub mod user;
 pub mod v1;
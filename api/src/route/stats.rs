@@ -0,0 +1,12 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::{checkout::show_daily_checkouts, stats::show_library_stats};
+
+pub fn build_stats_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route("/", get(show_library_stats))
+        .route("/checkouts-daily", get(show_daily_checkouts));
+
+    Router::new().nest("/stats", routers)
+}
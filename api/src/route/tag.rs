@@ -0,0 +1,10 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::tag::show_tag_list;
+
+pub fn build_tag_routers() -> Router<AppRegistry> {
+    let routers = Router::new().route("/", get(show_tag_list));
+
+    Router::new().nest("/tags", routers)
+}
@@ -1,12 +1,14 @@
 use axum::{
-    routing::{delete, get, put},
+    routing::{get, put},
     Router,
 };
 use registry::AppRegistry;
 
+use crate::handler::book::show_my_book_list;
+use crate::handler::favorite::show_favorite_list;
 use crate::handler::user::{
-    change_password, change_role, delete_user, get_chekouts, get_current_user, list_users,
-    register_user,
+    change_password, change_role, delete_user, get_checkout_history, get_chekouts,
+    get_current_user, list_users, register_user, show_user,
 };
 
 pub fn build_user_router() -> Router<AppRegistry> {
@@ -14,7 +16,10 @@ pub fn build_user_router() -> Router<AppRegistry> {
         .route("/users/me", get(get_current_user))
         .route("/users/me/password", put(change_password))
         .route("/users/me/checkouts", get(get_chekouts))
+        .route("/users/me/checkout-history", get(get_checkout_history))
+        .route("/users/me/favorites", get(show_favorite_list))
+        .route("/users/me/books", get(show_my_book_list))
         .route("/users", get(list_users).post(register_user))
-        .route("/users/:user_id", delete(delete_user))
+        .route("/users/:user_id", get(show_user).delete(delete_user))
         .route("/users/:user_id/role", put(change_role))
 }
@@ -2,14 +2,22 @@ use axum::Router;
 use registry::AppRegistry;
 
 use super::{
-    book::build_book_routers, health::build_health_check_routers, user::build_user_router,
+    book::build_book_routers, checkout::build_checkout_routers, health::build_health_check_routers,
+    stats::build_stats_routers, tag::build_tag_routers, user::build_user_router,
 };
 
 pub fn routes() -> Router<AppRegistry> {
     let router = Router::new()
-        .merge(build_health_check_routers())
         .merge(build_book_routers())
+        .merge(build_checkout_routers())
+        .merge(build_stats_routers())
+        .merge(build_tag_routers())
         .merge(build_user_router());
 
     Router::new().nest("/api/v1", router)
 }
+
+// ヘルスチェックにはタイムアウトなどを適用しないよう、他の API とは別に組み立てる
+pub fn health_routes() -> Router<AppRegistry> {
+    Router::new().nest("/api/v1", build_health_check_routers())
+}
@@ -3,7 +3,8 @@ pub struct CreateBook {
     pub title: String,
     pub author: String,
     pub isbn: String,
-    pub description: String,
+    // 説明のない蔵書は None（NULL）で登録する
+    pub description: Option<String>,
 }
 
 #[derive(Debug)]
@@ -12,7 +13,22 @@ pub struct UpdateBook {
     pub title: String,
     pub author: String,
     pub isbn: String,
-    pub description: String,
+    // None の場合は説明を消去する（NULL で保存する）
+    pub description: Option<String>,
+    pub requested_user: UserId,
+    // 更新前に取得した蔵書の版番号。現在の版と異なる場合は更新しない
+    pub version: i32,
+}
+
+// 指定された項目だけを更新する。None の項目は変更しない
+// description は NULL 許容のため、Some(None) で値を消去する
+#[derive(Debug)]
+pub struct PatchBook {
+    pub book_id: BookId,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub isbn: Option<String>,
+    pub description: Option<Option<String>>,
     pub requested_user: UserId,
 }
 
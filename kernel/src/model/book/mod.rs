@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
+use shared::config::SearchField;
 
 use crate::model::{
-    id::{BookId, CheckoutId},
+    id::{BookId, CheckoutId, UserId},
     user::{BookOwner, CheckoutUser},
 };
 
@@ -13,14 +14,76 @@ pub struct Book {
     pub title: String,
     pub author: String,
     pub isbn: String,
-    pub description: String,
+    pub description: Option<String>,
     pub owner: BookOwner,
     pub checkout: Option<Checkout>,
+    // 名前の昇順に並べたタグ
+    pub tags: Vec<String>,
+    // 更新のたびに 1 ずつ増える版番号。更新時に取得した版を渡して競合を検知する
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
 }
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BookListOptions {
     pub limit: i64,
     pub offset: i64,
+    // 指定した場合はそのユーザーが所有する蔵書だけに絞り込む
+    pub owned_by: Option<UserId>,
+    // true の場合は貸出中でない蔵書だけに絞り込む
+    pub only_available: bool,
+    // 指定した場合は登録日時がこの範囲（両端を含む）の蔵書だけに絞り込む
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    // 空でない場合は tag_match に従って、これらのタグが付いた蔵書だけに絞り込む
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    pub sort: BookSortKey,
+    pub order: SortOrder,
+    // true の場合は総件数を数えず、PaginatedList の total を None にする。
+    // 総件数を数えるには条件に合う蔵書をすべて読む必要があるため、不要な場合は省略できるようにする
+    pub skip_total: bool,
+}
+
+// 蔵書一覧の並び替えに使う項目。未指定の場合は登録日時で並べる
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BookSortKey {
+    #[default]
+    CreatedAt,
+    Title,
+    Author,
+}
+
+impl BookSortKey {
+    // 並び順を指定しなかった場合の向き。登録日時は新しい順、文字列の項目は昇順とする
+    pub fn default_order(&self) -> SortOrder {
+        match self {
+            Self::CreatedAt => SortOrder::Desc,
+            Self::Title | Self::Author => SortOrder::Asc,
+        }
+    }
+}
+
+// 複数のタグで絞り込むときの条件。All はすべてのタグ、Any はいずれかのタグが付いた蔵書に絞り込む
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    #[default]
+    All,
+    Any,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// キーワード検索の条件。fields に含まれる項目のいずれかにすべての単語を含む蔵書を探す
+#[derive(Debug)]
+pub struct BookSearchOptions {
+    pub terms: Vec<String>,
+    pub fields: Vec<SearchField>,
 }
 
 #[derive(Debug)]
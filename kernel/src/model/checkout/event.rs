@@ -17,3 +17,13 @@ pub struct UpdateReturned {
     pub returned_by: UserId,
     pub returned_at: DateTime<Utc>,
 }
+
+// 複数の蔵書をまとめて貸し出す。
+// best_effort が false の場合は、1冊でも貸し出せなければすべての貸出を取り消す
+#[derive(new)]
+pub struct CreateCheckouts {
+    pub book_ids: Vec<BookId>,
+    pub checked_out_by: UserId,
+    pub checked_out_at: DateTime<Utc>,
+    pub best_effort: bool,
+}
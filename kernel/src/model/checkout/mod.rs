@@ -1,5 +1,5 @@
 use crate::model::id::{BookId, CheckoutId, UserId};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
 pub mod event;
 
@@ -12,6 +12,72 @@ pub struct Checkout {
     pub book: CheckoutBook,
 }
 
+// 貸出の一覧の絞り込み条件。指定しない条件では絞り込まない
+#[derive(Debug, Default)]
+pub struct CheckoutListFilter {
+    // true の場合は返却済みの貸出を含めない
+    pub active_only: bool,
+    pub user_id: Option<UserId>,
+    pub book_id: Option<BookId>,
+}
+
+// まとめて貸し出した結果。remaining は貸し出した後にあと何冊借りられるか
+#[derive(Debug)]
+pub struct CheckoutBatch {
+    pub items: Vec<CheckoutBatchItem>,
+    pub remaining: i64,
+}
+
+// まとめて貸し出したときの蔵書ごとの結果
+#[derive(Debug)]
+pub struct CheckoutBatchItem {
+    pub book_id: BookId,
+    pub outcome: CheckoutOutcome,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheckoutOutcome {
+    CheckedOut(CheckoutId),
+    NotFound,
+    AlreadyCheckedOut,
+    // 他のユーザーが先に予約している
+    Reserved,
+    LimitExceeded,
+}
+
+// 1日あたりの貸出件数（日付は UTC で区切る）
+#[derive(Debug, PartialEq, Eq)]
+pub struct DailyCount {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+// 長期間返却されていない（紛失の疑いがある）貸出
+#[derive(Debug)]
+pub struct LostCheckout {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub checked_out_by: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub days_overdue: i64,
+}
+
+// 返却期限を過ぎても返却されていない貸出
+#[derive(Debug)]
+pub struct OverdueCheckout {
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub title: String,
+    pub checked_out_by: UserId,
+    pub user_name: String,
+    pub email: String,
+    pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
 #[derive(Debug)]
 pub struct CheckoutBook {
     pub book_id: BookId,
@@ -61,3 +61,4 @@ macro_rules! define_id {
 define_id!(BookId);
 define_id!(UserId);
 define_id!(CheckoutId);
+define_id!(ReservationId);
@@ -1,7 +1,8 @@
 #[derive(Debug)]
 
 pub struct PaginatedList<T> {
-    pub total: i64,
+    // 総件数を数えなかった場合は None になる
+    pub total: Option<i64>,
     pub limit: i64,
     pub offset: i64,
     pub items: Vec<T>,
@@ -12,3 +13,12 @@ impl<T> PaginatedList<T> {
         self.items
     }
 }
+
+// キーセット方式で取得した一覧。next_cursor を次の取得時に渡すと続きを取得できる。
+// 続きがない場合、next_cursor は None になる
+#[derive(Debug)]
+pub struct CursorList<T, C> {
+    pub limit: i64,
+    pub items: Vec<T>,
+    pub next_cursor: Option<C>,
+}
@@ -1,7 +1,11 @@
 pub mod auth;
 pub mod book;
 pub mod checkout;
+pub mod favorite;
 pub mod id;
 pub mod list;
+pub mod reservation;
 pub mod role;
+pub mod stats;
+pub mod tag;
 pub mod user;
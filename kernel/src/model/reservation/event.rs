@@ -0,0 +1,15 @@
+use derive_new::new;
+
+use crate::model::id::{BookId, UserId};
+
+#[derive(new)]
+pub struct CreateReservation {
+    pub book_id: BookId,
+    pub user_id: UserId,
+}
+
+#[derive(new)]
+pub struct DeleteReservation {
+    pub book_id: BookId,
+    pub user_id: UserId,
+}
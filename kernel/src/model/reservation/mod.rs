@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+
+use crate::model::{
+    id::{BookId, ReservationId},
+    user::ReservationUser,
+};
+
+pub mod event;
+
+// 蔵書の順番待ちに並んでいる予約。position は先頭が 1
+#[derive(Debug)]
+pub struct Reservation {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub reserved_by: ReservationUser,
+    pub position: i64,
+    pub reserved_at: DateTime<Utc>,
+}
+
+// 予約と、その蔵書の順番待ちの中での位置（先頭が 1）
+#[derive(Debug)]
+pub struct ReservationPosition {
+    pub reservation_id: ReservationId,
+    pub book_id: BookId,
+    pub position: i64,
+    // 今回の操作で新たに予約された場合は true、予約済みだった場合は false
+    pub created: bool,
+}
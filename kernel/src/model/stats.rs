@@ -0,0 +1,8 @@
+// 管理者のダッシュボードに表示する蔵書・貸出・ユーザーの件数
+#[derive(Debug, PartialEq, Eq)]
+pub struct LibraryStats {
+    // 削除済みの蔵書は含めない
+    pub total_books: i64,
+    pub active_checkouts: i64,
+    pub total_users: i64,
+}
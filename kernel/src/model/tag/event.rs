@@ -0,0 +1,15 @@
+use derive_new::new;
+
+use crate::model::id::BookId;
+
+#[derive(new)]
+pub struct AttachTag {
+    pub book_id: BookId,
+    pub name: String,
+}
+
+#[derive(new)]
+pub struct DetachTag {
+    pub book_id: BookId,
+    pub name: String,
+}
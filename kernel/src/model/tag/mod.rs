@@ -0,0 +1,8 @@
+pub mod event;
+
+// タグと、そのタグが付いた（削除されていない）蔵書の数
+#[derive(Debug)]
+pub struct Tag {
+    pub name: String,
+    pub book_count: i64,
+}
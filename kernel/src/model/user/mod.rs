@@ -10,10 +10,18 @@ pub struct User {
     pub role: Role,
 }
 
+// ユーザーの詳細画面に表示する情報。貸出中の冊数をあわせて返す
+#[derive(Debug)]
+pub struct UserDetail {
+    pub user: User,
+    pub active_checkout_count: i64,
+}
+
 #[derive(Debug)]
 pub struct BookOwner {
     pub id: UserId,
     pub name: String,
+    pub email: String,
 }
 
 #[derive(Debug)]
@@ -21,3 +29,9 @@ pub struct CheckoutUser {
     pub id: UserId,
     pub name: String,
 }
+
+#[derive(Debug)]
+pub struct ReservationUser {
+    pub id: UserId,
+    pub name: String,
+}
@@ -3,18 +3,44 @@ use shared::error::AppResult;
 
 use crate::model::{
     book::{
-        event::{CreateBook, DeleteBook, UpdateBook},
-        Book, BookListOptions,
+        event::{CreateBook, DeleteBook, PatchBook, UpdateBook},
+        Book, BookListOptions, BookSearchOptions,
     },
     id::{BookId, UserId},
-    list::PaginatedList,
+    list::{CursorList, PaginatedList},
 };
 
 #[async_trait]
 pub trait BookRepository: Send + Sync {
     async fn create(&self, event: CreateBook, user_id: UserId) -> AppResult<()>;
+    async fn create_many(&self, events: Vec<CreateBook>, user_id: UserId)
+        -> AppResult<Vec<BookId>>;
+    async fn create_many_partial(
+        &self,
+        events: Vec<CreateBook>,
+        user_id: UserId,
+    ) -> AppResult<Vec<Option<BookId>>>;
     async fn find_all(&self, options: BookListOptions) -> AppResult<PaginatedList<Book>>;
+    async fn find_all_after(
+        &self,
+        cursor: Option<BookId>,
+        limit: i64,
+    ) -> AppResult<CursorList<Book, BookId>>;
+    async fn find_favorites(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Book>>;
+    async fn find_by_keyword(
+        &self,
+        search: BookSearchOptions,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Book>>;
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>>;
+    async fn find_by_ids(&self, book_ids: &[BookId]) -> AppResult<Vec<Book>>;
+    async fn find_by_isbn(&self, isbn: &str) -> AppResult<Option<Book>>;
     async fn update(&self, event: UpdateBook) -> AppResult<()>;
+    async fn patch(&self, event: PatchBook) -> AppResult<()>;
     async fn delete(&self, event: DeleteBook) -> AppResult<()>;
+    async fn restore(&self, book_id: BookId) -> AppResult<()>;
 }
@@ -1,19 +1,47 @@
 use crate::model::{
-    auth::event,
+    book::BookListOptions,
     checkout::{
-        event::{CreateCheckout, UpdateReturned},
-        Checkout,
+        event::{CreateCheckout, CreateCheckouts, UpdateReturned},
+        Checkout, CheckoutBatch, CheckoutListFilter, DailyCount, LostCheckout, OverdueCheckout,
     },
-    id::{BookId, UserId},
+    id::{BookId, CheckoutId, UserId},
+    list::PaginatedList,
 };
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
 use shared::error::AppResult;
 
 #[async_trait]
 pub trait CheckoutRepository: Send + Sync {
-    async fn create(&self, event: CreateCheckout) -> AppResult<()>;
+    // 貸し出した後に、あと何冊借りられるかを返す
+    async fn create(&self, event: CreateCheckout) -> AppResult<i64>;
+    async fn create_batch(&self, event: CreateCheckouts) -> AppResult<CheckoutBatch>;
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>;
+    async fn cancel(&self, checkout_id: CheckoutId, book_id: BookId) -> AppResult<()>;
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>>;
     async fn find_unreturned_by_user_id(&self, user_id: UserId) -> AppResult<Vec<Checkout>>;
-    async fn find_history_by_book_id(&self, book_id: BookId) -> AppResult<Vec<Checkout>>;
+    async fn find_active_by_user(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>>;
+    async fn find_all(
+        &self,
+        filter: CheckoutListFilter,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>>;
+    async fn find_history_by_book_id(
+        &self,
+        book_id: BookId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>>;
+    async fn find_history_by_user(
+        &self,
+        user_id: UserId,
+        options: BookListOptions,
+    ) -> AppResult<PaginatedList<Checkout>>;
+    async fn count_remaining_by_user_id(&self, user_id: UserId) -> AppResult<i64>;
+    async fn find_long_overdue(&self, days: i64) -> AppResult<Vec<LostCheckout>>;
+    async fn find_overdue(&self, as_of: DateTime<Utc>) -> AppResult<Vec<OverdueCheckout>>;
+    async fn daily_counts(&self, from: NaiveDate, to: NaiveDate) -> AppResult<Vec<DailyCount>>;
 }
@@ -0,0 +1,20 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    favorite::event::{CreateFavorite, DeleteFavorite},
+    id::{BookId, UserId},
+};
+
+#[async_trait]
+pub trait FavoriteRepository: Send + Sync {
+    async fn create(&self, event: CreateFavorite) -> AppResult<()>;
+    async fn delete(&self, event: DeleteFavorite) -> AppResult<()>;
+    async fn find_favorite_book_ids(
+        &self,
+        user_id: UserId,
+        book_ids: &[BookId],
+    ) -> AppResult<HashSet<BookId>>;
+}
@@ -1,6 +1,14 @@
 use async_trait::async_trait;
+use shared::error::AppResult;
 
 #[async_trait]
 pub trait HealthCheckRepository: Send + Sync {
-    async fn check_db(&self) -> bool;
+    async fn check_db(&self) -> AppResult<()>;
+}
+
+// readiness チェックの対象となる依存先。依存先ごとに実装し、自身の名前と稼働状況を返す
+#[async_trait]
+pub trait DependencyCheck: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn is_ready(&self) -> bool;
 }
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::BookId,
+    reservation::{
+        event::{CreateReservation, DeleteReservation},
+        Reservation, ReservationPosition,
+    },
+};
+
+#[async_trait]
+pub trait ReservationRepository: Send + Sync {
+    async fn create(&self, event: CreateReservation) -> AppResult<ReservationPosition>;
+    async fn find_queue(&self, book_id: BookId) -> AppResult<Vec<Reservation>>;
+    async fn cancel(&self, event: DeleteReservation) -> AppResult<()>;
+}
@@ -0,0 +1,9 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::stats::LibraryStats;
+
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    async fn library_stats(&self) -> AppResult<LibraryStats>;
+}
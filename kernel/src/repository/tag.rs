@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::tag::{
+    event::{AttachTag, DetachTag},
+    Tag,
+};
+
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    async fn attach(&self, event: AttachTag) -> AppResult<()>;
+    async fn detach(&self, event: DetachTag) -> AppResult<()>;
+    async fn list(&self) -> AppResult<Vec<Tag>>;
+}
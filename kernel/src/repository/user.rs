@@ -5,16 +5,22 @@ use crate::model::{
     id::UserId,
     user::{
         event::{CreateUser, DeleteUser, UpdateUserPassword, UpdateUserRole},
-        User,
+        User, UserDetail,
     },
 };
 
 #[async_trait]
 pub trait UserRepository: Send + Sync {
     async fn find_current_user(&self, current_user_id: UserId) -> AppResult<Option<User>>;
+    async fn find_by_id(&self, user_id: UserId) -> AppResult<Option<User>>;
+    // メールアドレスは大文字・小文字を区別せずに照合する
+    async fn find_by_email(&self, email: &str) -> AppResult<Option<User>>;
+    async fn find_detail_by_id(&self, user_id: UserId) -> AppResult<Option<UserDetail>>;
     async fn find_all(&self) -> AppResult<Vec<User>>;
     async fn create(&self, event: CreateUser) -> AppResult<User>;
     async fn update_password(&self, event: UpdateUserPassword) -> AppResult<()>;
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()>;
+    // 貸出中の蔵書があるユーザーは削除できない（Conflict）。
+    // 所有する蔵書（論理削除したものを含む）や貸出履歴・お気に入り・予約は合わせて削除される
     async fn delete(&self, event: DeleteUser) -> AppResult<()>;
 }
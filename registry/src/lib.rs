@@ -1,16 +1,26 @@
 use std::sync::Arc;
 
-use adapter::database::model::checkout;
 use adapter::redis::RedisClient;
 use adapter::repository::auth::AuthRepositoryImpl;
 use adapter::repository::book::BookRepositoryImpl;
 use adapter::repository::checkout::CheckoutRepositoryImpl;
+use adapter::repository::favorite::FavoriteRepositoryImpl;
+use adapter::repository::reservation::ReservationRepositoryImpl;
+use adapter::repository::stats::StatsRepositoryImpl;
+use adapter::repository::tag::TagRepositoryImpl;
 use adapter::repository::user::UserRepositoryImpl;
-use adapter::{database::ConnectionPool, repository::health::HealthCheckRepositoryImpl};
+use adapter::{
+    database::ConnectionPool,
+    repository::health::{DatabaseDependencyCheck, HealthCheckRepositoryImpl},
+};
 use kernel::repository::auth::AuthRepository;
 use kernel::repository::book::BookRepository;
 use kernel::repository::checkout::CheckoutRepository;
-use kernel::repository::health::HealthCheckRepository;
+use kernel::repository::favorite::FavoriteRepository;
+use kernel::repository::health::{DependencyCheck, HealthCheckRepository};
+use kernel::repository::reservation::ReservationRepository;
+use kernel::repository::stats::StatsRepository;
+use kernel::repository::tag::TagRepository;
 use kernel::repository::user::UserRepository;
 use shared::config::AppConfig;
 
@@ -21,6 +31,12 @@ pub struct AppRegistry {
     auth_repository: Arc<dyn AuthRepository>,
     user_repository: Arc<dyn UserRepository>,
     checkout_repository: Arc<dyn CheckoutRepository>,
+    favorite_repository: Arc<dyn FavoriteRepository>,
+    reservation_repository: Arc<dyn ReservationRepository>,
+    stats_repository: Arc<dyn StatsRepository>,
+    tag_repository: Arc<dyn TagRepository>,
+    dependency_checks: Vec<Arc<dyn DependencyCheck>>,
+    app_config: Arc<AppConfig>,
 }
 
 impl AppRegistry {
@@ -30,14 +46,33 @@ impl AppRegistry {
         app_config: AppConfig,
     ) -> Self {
         let health_check_repository = Arc::new(HealthCheckRepositoryImpl::new(pool.clone()));
-        let book_repository = Arc::new(BookRepositoryImpl::new(pool.clone()));
+        let book_repository = BookRepositoryImpl::new(pool.clone());
+        let book_repository = Arc::new(match app_config.cache.book_ttl {
+            Some(ttl) => book_repository.with_cache(redis_client.clone(), ttl),
+            None => book_repository,
+        });
         let auth_repository = Arc::new(AuthRepositoryImpl::new(
             pool.clone(),
             redis_client.clone(),
             app_config.auth.ttl,
         ));
-        let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
-        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(pool.clone()));
+        let user_repository = UserRepositoryImpl::new(pool.clone());
+        let user_repository = Arc::new(match app_config.cache.book_ttl {
+            Some(_) => user_repository.with_book_cache(redis_client.clone()),
+            None => user_repository,
+        });
+        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout.max_per_user,
+            app_config.checkout.loan_period_days,
+        ));
+        let favorite_repository = Arc::new(FavoriteRepositoryImpl::new(pool.clone()));
+        let reservation_repository = Arc::new(ReservationRepositoryImpl::new(pool.clone()));
+        let stats_repository = Arc::new(StatsRepositoryImpl::new(pool.clone()));
+        let tag_repository = Arc::new(TagRepositoryImpl::new(pool.clone()));
+        // readiness チェックの対象。依存先が増えたらここに追加する
+        let dependency_checks: Vec<Arc<dyn DependencyCheck>> =
+            vec![Arc::new(DatabaseDependencyCheck::new(pool.clone()))];
 
         Self {
             health_check_repository,
@@ -45,6 +80,12 @@ impl AppRegistry {
             auth_repository,
             user_repository,
             checkout_repository,
+            favorite_repository,
+            reservation_repository,
+            stats_repository,
+            tag_repository,
+            dependency_checks,
+            app_config: Arc::new(app_config),
         }
     }
 
@@ -67,4 +108,28 @@ impl AppRegistry {
     pub fn checkout_repository(&self) -> Arc<dyn CheckoutRepository> {
         self.checkout_repository.clone()
     }
+
+    pub fn favorite_repository(&self) -> Arc<dyn FavoriteRepository> {
+        self.favorite_repository.clone()
+    }
+
+    pub fn reservation_repository(&self) -> Arc<dyn ReservationRepository> {
+        self.reservation_repository.clone()
+    }
+
+    pub fn stats_repository(&self) -> Arc<dyn StatsRepository> {
+        self.stats_repository.clone()
+    }
+
+    pub fn tag_repository(&self) -> Arc<dyn TagRepository> {
+        self.tag_repository.clone()
+    }
+
+    pub fn dependency_checks(&self) -> Vec<Arc<dyn DependencyCheck>> {
+        self.dependency_checks.clone()
+    }
+
+    pub fn config(&self) -> Arc<AppConfig> {
+        self.app_config.clone()
+    }
 }
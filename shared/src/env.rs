@@ -1,21 +1,114 @@
 use std::env;
-use strum::EnumString;
 
-#[derive(Default, EnumString)]
+use anyhow::{anyhow, Result};
+use strum::{Display, EnumString};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumString)]
 #[strum(serialize_all = "lowercase")]
 pub enum Environment {
     #[default]
     Development,
     Production,
 }
-pub fn which() -> Environment {
+
+// 環境変数 ENV が設定されていればそれを優先し、なければビルドのプロファイルから決める
+// （デバッグビルドは development、リリースビルドは production）。
+// 想定していない値が設定されている場合は、既定値で動かさずにエラーにする
+pub fn which() -> Result<Environment> {
+    resolve(env::var("ENV").ok().filter(|v| !v.is_empty()).as_deref())
+}
+
+fn resolve(value: Option<&str>) -> Result<Environment> {
     #[cfg(debug_assertions)]
     let default_env = Environment::Development;
     #[cfg(not(debug_assertions))]
     let default_env = Environment::Production;
 
-    match env::var("ENV") {
-        Err(_) => default_env,
-        Ok(v) => v.parse().unwrap_or(default_env),
+    match value {
+        None => Ok(default_env),
+        Some(v) => v
+            .parse()
+            .map_err(|_| anyhow!("ENV must be `development` or `production`, got `{v}`")),
+    }
+}
+
+// ログの出力形式。本番環境ではログ基盤で集計しやすいよう JSON で出力する
+#[derive(Debug, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+// LOG_FORMAT が設定されていればそれを優先し、なければ環境ごとのデフォルトを返す。
+// ENV と同じく、想定していない値が設定されている場合はエラーにする
+pub fn log_format(environment: &Environment) -> Result<LogFormat> {
+    resolve_log_format(
+        environment,
+        env::var("LOG_FORMAT")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .as_deref(),
+    )
+}
+
+fn resolve_log_format(environment: &Environment, value: Option<&str>) -> Result<LogFormat> {
+    let default_format = match environment {
+        Environment::Development => LogFormat::Pretty,
+        Environment::Production => LogFormat::Json,
+    };
+
+    match value {
+        None => Ok(default_format),
+        Some(v) => v
+            .parse()
+            .map_err(|_| anyhow!("LOG_FORMAT must be `pretty` or `json`, got `{v}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_environment() {
+        assert_eq!(
+            resolve(Some("development")).unwrap(),
+            Environment::Development
+        );
+        assert_eq!(
+            resolve(Some("production")).unwrap(),
+            Environment::Production
+        );
+        // テストはデバッグビルドで実行されるため、未設定の場合は development になる
+        assert_eq!(resolve(None).unwrap(), Environment::Development);
+
+        let err = resolve(Some("staging")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ENV must be `development` or `production`, got `staging`"
+        );
+        assert!(resolve(Some("Production")).is_err());
+    }
+
+    #[test]
+    fn resolve_log_format_from_value() {
+        let dev = Environment::Development;
+        assert_eq!(resolve_log_format(&dev, None).unwrap(), LogFormat::Pretty);
+        assert_eq!(
+            resolve_log_format(&Environment::Production, None).unwrap(),
+            LogFormat::Json
+        );
+        assert_eq!(
+            resolve_log_format(&dev, Some("json")).unwrap(),
+            LogFormat::Json
+        );
+
+        // 綴りを間違えた場合は既定の形式にせず、LOG_FORMAT を名指ししてエラーにする
+        let err = resolve_log_format(&dev, Some("jsno")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "LOG_FORMAT must be `pretty` or `json`, got `jsno`"
+        );
     }
 }
@@ -1,46 +1,158 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract::rejection::PathRejection, http::StatusCode, response::IntoResponse, Json};
+use serde::Serialize;
 use thiserror::Error;
 
+// 各バリアントのステータスコードは into_response を参照
 #[derive(Error, Debug)]
 pub enum AppError {
+    // 422: 形式は正しいが、業務上受け付けられない操作（返却できない貸出など）
     #[error("{0}")]
     UnprocessableEntity(String),
+    // 404: 対象のエンティティが存在しない
     #[error("{0}")]
     EntityNotFound(String),
+    // 409: 貸し出そうとした蔵書が既に貸出中
+    #[error("{0}")]
+    CheckoutConflict(String),
+    // 409: 貸し出そうとした蔵書を他のユーザーが先に予約している
+    #[error("{0}")]
+    BookReserved(String),
+    // 409: 登録しようとしたメールアドレスが既に使われている
+    #[error("{0}")]
+    EmailAlreadyExists(String),
+    // 409: 登録・更新しようとした ISBN の蔵書が既に存在する
+    #[error("{0}")]
+    DuplicateIsbn(String),
+    // 409: 更新しようとした蔵書が、取得した後に他のユーザーによって更新されている
+    #[error("{0}")]
+    ConflictingUpdate(String),
+    // 409: 一意制約に違反する（既に登録されているデータと重複する）
+    #[error("{0}")]
+    Conflict(String),
+    // 422: ユーザーの貸出中の冊数が上限に達している
+    #[error("{0}")]
+    CheckoutLimitExceeded(String),
+    // 422: 形式は正しいが、値がバリデーションを満たさない（空のタイトル、範囲外の値など）
     #[error("{0}")]
     ValidationError(#[from] garde::Report),
+    // 500
     #[error("トランザクションを実行できませんでした。")]
     TransactionError(#[source] sqlx::Error),
+    // 500
     #[error("データベース処理実行中にエラーが発生しました。")]
     SpecificOperationError(#[source] sqlx::Error),
+    // 500
     #[error("No rows affected: {0}")]
     NoRowsAffectedError(String),
+    // 500
     #[error("{0}")]
     KeyValueStoreError(#[from] redis::RedisError),
+    // 500
     #[error("{0}")]
     BcryptError(#[from] bcrypt::BcryptError),
+    // 400: UUID として解釈できない文字列が渡された
     #[error("{0}")]
     ConvertToUuidError(#[from] uuid::Error),
+    // 400: パスパラメータを指定された型として解釈できない（UUID でない ID など）
+    #[error("パスパラメータが正しくありません: {0}")]
+    InvalidPathParameter(String),
+    // 401: 認証情報（パスワードやアクセストークン）が誤っている、または有効期限切れ
     #[error("ログインに失敗しました")]
     UnauthenticatedError,
+    // 401
     #[error("認可情報が誤っています")]
     UnauthorizedError,
+    // 403
     #[error("許可されていない操作です")]
     ForbiddenOperation,
+    // 405: パスは存在するが、そのメソッドには対応していない
+    #[error("{0}")]
+    MethodNotAllowed(String),
+    // 429: 一定時間内のリクエスト数が上限を超えた
+    #[error("リクエストが多すぎます。しばらく待ってから再度お試しください")]
+    TooManyRequests,
+    // 504: 処理が制限時間内に終わらなかった
+    #[error("処理がタイムアウトしました")]
+    RequestTimeout,
+    // 500
     #[error("{0}")]
     ConversionEntityError(String),
 }
 
+impl AppError {
+    // 見つからなかったエンティティの種類と ID を含めた 404 のエラーを作る。
+    // ハンドラやリポジトリごとに文言が揺れないよう、EntityNotFound はなるべくこれを通して作る
+    pub fn not_found(entity: &str, id: impl std::fmt::Display) -> Self {
+        AppError::EntityNotFound(format!("{entity} with id {id} not found"))
+    }
+
+    // garde の検証を通さずに見つかった入力の誤りを、ValidationError と同じ形（フィールド名とメッセージ）で返す
+    pub fn invalid_field(field: &str, message: &str) -> Self {
+        let mut report = garde::Report::new();
+        report.append(garde::Path::new(field), garde::Error::new(message));
+        AppError::ValidationError(report)
+    }
+
+    // クライアントがエラーの種類で処理を分けられるよう、バリアントごとに固定の文字列を返す
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::UnprocessableEntity(_) => "unprocessable_entity",
+            AppError::EntityNotFound(_) => "entity_not_found",
+            AppError::CheckoutConflict(_) => "checkout_conflict",
+            AppError::BookReserved(_) => "book_reserved",
+            AppError::CheckoutLimitExceeded(_) => "checkout_limit_exceeded",
+            AppError::EmailAlreadyExists(_) => "email_already_exists",
+            AppError::DuplicateIsbn(_) => "duplicate_isbn",
+            AppError::ConflictingUpdate(_) => "conflicting_update",
+            AppError::Conflict(_) => "conflict",
+            AppError::ValidationError(_) => "validation_error",
+            AppError::TransactionError(_) => "transaction_error",
+            AppError::SpecificOperationError(_) => "database_error",
+            AppError::NoRowsAffectedError(_) => "no_rows_affected",
+            AppError::KeyValueStoreError(_) => "key_value_store_error",
+            AppError::BcryptError(_) => "bcrypt_error",
+            AppError::ConvertToUuidError(_) => "invalid_uuid",
+            AppError::InvalidPathParameter(_) => "invalid_path_parameter",
+            AppError::UnauthenticatedError => "unauthenticated",
+            AppError::UnauthorizedError => "unauthorized",
+            AppError::ForbiddenOperation => "forbidden_operation",
+            AppError::MethodNotAllowed(_) => "method_not_allowed",
+            AppError::TooManyRequests => "too_many_requests",
+            AppError::RequestTimeout => "request_timeout",
+            AppError::ConversionEntityError(_) => "conversion_entity_error",
+        }
+    }
+}
+
 impl IntoResponse for AppError {
+    // 入力の誤りは次のように区別する。
+    // - 400: 構文として解釈できない（JSON として不正、UUID として不正など）
+    // - 422: 構文としては正しいが、値の意味が受け付けられない
+    // なお、リクエストボディの解釈の失敗は axum のエクストラクタが同じ基準で
+    // 400（構文エラー）または 422（型や必須項目の不一致）を返す。
+    // パスの解釈の失敗は api の Path エクストラクタが InvalidPathParameter に変換する
     fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
-            AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        let status_code = match &self {
+            AppError::UnprocessableEntity(_)
+            | AppError::CheckoutLimitExceeded(_)
+            | AppError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
-            AppError::ValidationError(_) | AppError::ConvertToUuidError(_) => {
+            AppError::CheckoutConflict(_)
+            | AppError::BookReserved(_)
+            | AppError::EmailAlreadyExists(_)
+            | AppError::DuplicateIsbn(_)
+            | AppError::ConflictingUpdate(_)
+            | AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::ConvertToUuidError(_) | AppError::InvalidPathParameter(_) => {
                 StatusCode::BAD_REQUEST
             }
-            AppError::UnauthenticatedError | AppError::ForbiddenOperation => StatusCode::FORBIDDEN,
-            AppError::UnauthorizedError => StatusCode::UNAUTHORIZED,
+            AppError::ForbiddenOperation => StatusCode::FORBIDDEN,
+            AppError::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            AppError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            AppError::RequestTimeout => StatusCode::GATEWAY_TIMEOUT,
+            AppError::UnauthenticatedError | AppError::UnauthorizedError => {
+                StatusCode::UNAUTHORIZED
+            }
             e @ (AppError::TransactionError(_)
             | AppError::SpecificOperationError(_)
             | AppError::NoRowsAffectedError(_)
@@ -55,9 +167,219 @@ impl IntoResponse for AppError {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
         };
-        status_code.into_response()
+        (status_code, Json(ErrorResponse::from(&self))).into_response()
+    }
+}
+
+// {"error": {"code": "...", "message": "..."}} の形式で返すエラーレスポンス
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+    // バリデーションエラーのときだけ、どの項目をどう直せばよいかを返す
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<FieldError>,
+}
+
+#[derive(Debug, Serialize)]
+struct FieldError {
+    field: String,
+    message: String,
+}
+
+impl From<&AppError> for ErrorResponse {
+    fn from(e: &AppError) -> Self {
+        let (message, fields) = match e {
+            AppError::ValidationError(report) => (
+                "入力値が正しくありません。".to_string(),
+                report
+                    .iter()
+                    .map(|(path, error)| FieldError {
+                        field: path.to_string(),
+                        message: error.to_string(),
+                    })
+                    .collect(),
+            ),
+            // サーバー側の問題は内部の情報を含みうるため、詳細はログにだけ出す
+            AppError::TransactionError(_)
+            | AppError::SpecificOperationError(_)
+            | AppError::NoRowsAffectedError(_)
+            | AppError::KeyValueStoreError(_)
+            | AppError::BcryptError(_)
+            | AppError::ConversionEntityError(_) => {
+                ("サーバー内部でエラーが発生しました。".to_string(), vec![])
+            }
+            e => (e.to_string(), vec![]),
+        };
+        Self {
+            error: ErrorBody {
+                code: e.code(),
+                message,
+                fields,
+            },
+        }
+    }
+}
+
+// axum の Path が返すエラーを、他の API と同じ形式のレスポンスにするための変換
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        match rejection {
+            PathRejection::FailedToDeserializePathParams(e) => {
+                AppError::InvalidPathParameter(e.body_text())
+            }
+            // ルート定義とハンドラの引数が食い違っている場合で、クライアントの誤りではない
+            e => AppError::ConversionEntityError(e.body_text()),
+        }
     }
 }
 
 // エラー型が `AppError` なものを扱える `Result` 型
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(e: AppError) -> StatusCode {
+        e.into_response().status()
+    }
+
+    #[test]
+    fn test_malformed_input_is_bad_request() {
+        let e = uuid::Uuid::parse_str("not-a-uuid").unwrap_err();
+        assert_eq!(status(e.into()), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            status(AppError::InvalidPathParameter("not-a-uuid".into())),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_semantically_invalid_input_is_unprocessable() {
+        let mut report = garde::Report::new();
+        report.append(
+            garde::Path::new("title"),
+            garde::Error::new("length is lower than 1"),
+        );
+        assert_eq!(
+            status(AppError::ValidationError(report)),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(AppError::UnprocessableEntity("cannot return".into())),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            status(AppError::CheckoutLimitExceeded("over the limit".into())),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    async fn body(e: AppError) -> anyhow::Result<serde_json::Value> {
+        let body = axum::body::to_bytes(e.into_response().into_body(), usize::MAX).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    #[test]
+    fn test_not_found() {
+        let err = AppError::not_found("Book", "9890736e-a4e4-461a-a77d-eac3517ef11b");
+        assert!(matches!(err, AppError::EntityNotFound(_)));
+        assert_eq!(
+            err.to_string(),
+            "Book with id 9890736e-a4e4-461a-a77d-eac3517ef11b not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_error_body() -> anyhow::Result<()> {
+        let mut report = garde::Report::new();
+        report.append(
+            garde::Path::new("title"),
+            garde::Error::new("length is lower than 1"),
+        );
+        let res = body(AppError::ValidationError(report)).await?;
+        assert_eq!(res["error"]["code"], "validation_error");
+        assert_eq!(res["error"]["fields"][0]["field"], "title");
+        assert_eq!(
+            res["error"]["fields"][0]["message"],
+            "length is lower than 1"
+        );
+
+        let res = body(AppError::EntityNotFound(
+            "書籍が見つかりませんでした。".into(),
+        ))
+        .await?;
+        assert_eq!(res["error"]["code"], "entity_not_found");
+        assert_eq!(res["error"]["message"], "書籍が見つかりませんでした。");
+        assert!(res["error"].get("fields").is_none());
+
+        // サーバー側のエラーは内部の情報を返さない
+        let res = body(AppError::NoRowsAffectedError("checkouts".into())).await?;
+        assert_eq!(res["error"]["code"], "no_rows_affected");
+        assert!(!res["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("checkouts"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_other_client_errors() {
+        assert_eq!(
+            status(AppError::EntityNotFound("book".into())),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status(AppError::CheckoutConflict("checked out".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(AppError::BookReserved("reserved".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(AppError::EmailAlreadyExists("registered".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(AppError::DuplicateIsbn("registered".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(AppError::ConflictingUpdate("updated".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            status(AppError::Conflict("duplicated".into())),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(status(AppError::ForbiddenOperation), StatusCode::FORBIDDEN);
+        assert_eq!(
+            status(AppError::MethodNotAllowed("POST".into())),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(
+            status(AppError::TooManyRequests),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            status(AppError::RequestTimeout),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(
+            status(AppError::UnauthorizedError),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(AppError::UnauthenticatedError),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// ローカル開発用にロール・管理者ユーザー・サンプルの蔵書を登録する。
+// 登録済みのデータはスキップするため、何度実行しても重複しない
+use adapter::{
+    database::{connect_database_with, migrate, seed_roles, wait_for_database},
+    repository::{book::BookRepositoryImpl, user::UserRepositoryImpl},
+};
+use anyhow::{Context, Result};
+use kernel::{
+    model::{
+        book::event::CreateBook,
+        id::UserId,
+        role::Role,
+        user::event::{CreateUser, UpdateUserRole},
+    },
+    repository::{book::BookRepository, user::UserRepository},
+};
+use shared::{config::AppConfig, error::AppError};
+
+// data/initial_setup.sql と同じ管理者ユーザー
+const DEFAULT_ADMIN_NAME: &str = "Eleazar Fig";
+const DEFAULT_ADMIN_EMAIL: &str = "eleazar.fig@example.com";
+const DEFAULT_ADMIN_PASSWORD: &str = "Pa55w0rd";
+
+// (タイトル, 著者, ISBN, 説明)
+const SAMPLE_BOOKS: &[(&str, &str, &str, &str)] = &[
+    (
+        "The Rust Programming Language",
+        "Steve Klabnik, Carol Nichols",
+        "9781718503106",
+        "Rust の公式入門書",
+    ),
+    (
+        "Programming Rust",
+        "Jim Blandy, Jason Orendorff, Leonora F. S. Tindall",
+        "9781492052593",
+        "システムプログラミングの観点から Rust を解説する",
+    ),
+    (
+        "Rust for Rustaceans",
+        "Jon Gjengset",
+        "9781718501850",
+        "中級者向けの Rust の解説書",
+    ),
+    (
+        "Zero To Production In Rust",
+        "Luca Palmieri",
+        "9798376542676",
+        "Rust による Web バックエンド開発の入門書",
+    ),
+    (
+        "Designing Data-Intensive Applications",
+        "Martin Kleppmann",
+        "9781449373320",
+        "データ処理システムの設計に関する解説書",
+    ),
+];
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt().init();
+
+    let app_config = AppConfig::new()?;
+    app_config.validate().context("Invalid configuration")?;
+    let pool = connect_database_with(&app_config.database)?;
+    wait_for_database(&app_config.database).await?;
+    if app_config.database.run_migrations {
+        migrate(&pool).await?;
+    }
+
+    seed_roles(&pool).await?;
+    tracing::info!("Roles seeded");
+
+    let user_repository = UserRepositoryImpl::new(pool.clone());
+    let admin_id = seed_admin(&user_repository).await?;
+
+    let book_repository = BookRepositoryImpl::new(pool);
+    seed_books(&book_repository, admin_id).await?;
+
+    Ok(())
+}
+
+// 管理者ユーザーを登録し、その ID を返す。同じメールアドレスのユーザーがいればそれを管理者にする
+async fn seed_admin(repository: &impl UserRepository) -> Result<UserId> {
+    let name = std::env::var("SEED_ADMIN_NAME").unwrap_or_else(|_| DEFAULT_ADMIN_NAME.into());
+    let email = std::env::var("SEED_ADMIN_EMAIL").unwrap_or_else(|_| DEFAULT_ADMIN_EMAIL.into());
+    let password =
+        std::env::var("SEED_ADMIN_PASSWORD").unwrap_or_else(|_| DEFAULT_ADMIN_PASSWORD.into());
+
+    let user_id = match repository
+        .create(CreateUser {
+            name,
+            email: email.clone(),
+            password,
+        })
+        .await
+    {
+        Ok(user) => {
+            tracing::info!(email, "Admin user created");
+            user.id
+        }
+        Err(AppError::EmailAlreadyExists(_)) => {
+            tracing::info!(email, "Admin user already exists, skipped");
+            repository
+                .find_by_email(&email)
+                .await?
+                .map(|u| u.id)
+                .context("Failed to find the existing admin user")?
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // UserRepository::create は User ロールで登録するため、管理者に変更する
+    repository
+        .update_role(UpdateUserRole {
+            user_id,
+            role: Role::Admin,
+        })
+        .await?;
+
+    Ok(user_id)
+}
+
+// ISBN が登録済みの蔵書はスキップする
+async fn seed_books(repository: &impl BookRepository, owner: UserId) -> Result<()> {
+    for (title, author, isbn, description) in SAMPLE_BOOKS {
+        if repository.find_by_isbn(isbn).await?.is_some() {
+            tracing::info!(isbn, "Book already exists, skipped");
+            continue;
+        }
+        let event = CreateBook {
+            title: title.to_string(),
+            author: author.to_string(),
+            isbn: isbn.to_string(),
+            description: Some(description.to_string()),
+        };
+        match repository.create(event, owner).await {
+            Ok(()) => tracing::info!(isbn, "Book created"),
+            // 上の確認の後に、同時に動いた別の処理が同じ ISBN の蔵書を登録した場合も登録済みとみなす。
+            // 削除済みの蔵書は一意制約の対象外のため、同じ ISBN でも新たに登録される
+            Err(AppError::DuplicateIsbn(_)) => {
+                tracing::info!(isbn, "Book already exists, skipped")
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}